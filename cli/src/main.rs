@@ -0,0 +1,476 @@
+//! Command-line front-end of ytinu. Manages the same games and mods as the GUI without a browser.
+
+use std::{
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use simplelog::{Config, LevelFilter, TermLogger, TerminalMode};
+use ytinu_core::{
+    data::MessageIcon, dev_link::LinkMode, dialog::Dialogs, instance::InstanceLock,
+    launch::LaunchOptions, pack::PackOptions, Manager,
+};
+
+static USAGE: &str = "Usage:\n\
+    ytinu-cli status\n\
+    ytinu-cli install <mod id>\n\
+    ytinu-cli update <mod id>\n\
+    ytinu-cli remove <mod id>\n\
+    ytinu-cli loader install|remove|enable|disable\n\
+    ytinu-cli launch [--vanilla] [--steam]\n\
+    ytinu-cli install-local <file.zip|file.dll> [--id <mod id>]\n\
+    ytinu-cli link <build dir> [--id <mod id>] [--copy] [--scripts] [--watch]\n\
+    ytinu-cli unlink <mod id>\n\
+    ytinu-cli pack <mod dir|plugin.dll> --url <download url> [--id <mod id>] [--version <version>] \
+    [--out <dir>] [--extract-to-root] [--dev]\n\
+    ytinu-cli lint-meta <metadata dir>\n\
+    ytinu-cli sign <file> --key <secret key file>";
+
+enum Command {
+    /// Print the set up games, the mod loader and installed mods
+    Status,
+    /// Install a mod from the metadata into the selected game
+    Install(String),
+    /// Update an installed mod to the version from the metadata
+    Update(String),
+    /// Uninstall a mod
+    Remove(String),
+    /// Install, remove, enable or disable the mod loader
+    Loader(LoaderAction),
+    /// Launch the selected game and wait for it to exit
+    Launch(LaunchOptions),
+    /// Install a mod from a local file into the selected game
+    InstallLocal { path: PathBuf, id: Option<String> },
+    /// Deploy a mod from a local build directory and optionally keep redeploying it
+    Link {
+        path: PathBuf,
+        id: Option<String>,
+        mode: LinkMode,
+        scripts: bool,
+        watch: bool,
+    },
+    /// Remove a linked mod
+    Unlink(String),
+    /// Build a release zip and print its metadata entry
+    Pack(PackOptions),
+    /// Check a metadata repository for errors
+    LintMeta(PathBuf),
+    /// Write a detached signature for a metadata file or release
+    Sign { path: PathBuf, key: PathBuf },
+}
+
+impl Command {
+    /// Whether the command loads and stores the state and has to lock the data directory
+    fn uses_state(&self) -> bool {
+        !matches!(
+            self,
+            Command::Pack(_) | Command::LintMeta(_) | Command::Sign { .. }
+        )
+    }
+}
+
+enum LoaderAction {
+    Install,
+    Remove,
+    Enable,
+    Disable,
+}
+
+/// Prints messages to the terminal and asks questions on stdin
+struct TerminalDialogs;
+
+impl Dialogs for TerminalDialogs {
+    fn message(&self, title: &str, message: &str, icon: MessageIcon) {
+        // Errors are already logged by the core library
+        if icon != MessageIcon::Error {
+            println!("{}:\n{}", title, message);
+        }
+    }
+
+    fn confirm(&self, title: &str, message: &str, _icon: MessageIcon, default: bool) -> bool {
+        print!(
+            "{}:\n{} [{}] ",
+            title,
+            message,
+            if default { "Y/n" } else { "y/N" }
+        );
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        match std::io::stdin().lock().read_line(&mut answer) {
+            Ok(0) | Err(_) => {
+                println!();
+                default
+            }
+            Ok(_) => match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => true,
+                "n" | "no" => false,
+                _ => default,
+            },
+        }
+    }
+}
+
+fn main() {
+    if let Err(error) = TermLogger::init(LevelFilter::Info, Config::default(), TerminalMode::Mixed)
+    {
+        eprintln!("Failed to setup logging: {}", error);
+    }
+    ytinu_core::dialog::set_handler(TerminalDialogs);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse(&args) {
+        Some(command) => command,
+        None => {
+            eprintln!("Invalid arguments. {}", USAGE);
+            std::process::exit(-1);
+        }
+    };
+    // Held until the process exits
+    let _lock = if command.uses_state() {
+        match InstanceLock::acquire() {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                log::error!("ytinu is running. Please close it first.");
+                std::process::exit(-1);
+            }
+            Err(error) => {
+                log::error!("{:#}", error);
+                std::process::exit(-1);
+            }
+        }
+    } else {
+        None
+    };
+    std::process::exit(run(command));
+}
+
+fn parse(args: &[String]) -> Option<Command> {
+    let mut args = args.iter().map(String::as_str);
+    let command = match args.next()? {
+        "status" => Command::Status,
+        "install" => Command::Install(args.next()?.to_string()),
+        "update" => Command::Update(args.next()?.to_string()),
+        "remove" => Command::Remove(args.next()?.to_string()),
+        "unlink" => Command::Unlink(args.next()?.to_string()),
+        "loader" => Command::Loader(match args.next()? {
+            "install" => LoaderAction::Install,
+            "remove" => LoaderAction::Remove,
+            "enable" => LoaderAction::Enable,
+            "disable" => LoaderAction::Disable,
+            _ => return None,
+        }),
+        "lint-meta" => Command::LintMeta(PathBuf::from(args.next()?)),
+        "launch" => {
+            let mut options = LaunchOptions::default();
+            for arg in args.by_ref() {
+                match arg {
+                    "--vanilla" => options.vanilla = true,
+                    "--steam" => options.steam = true,
+                    _ => return None,
+                }
+            }
+            Command::Launch(options)
+        }
+        "install-local" => {
+            let mut path = None;
+            let mut id = None;
+            while let Some(arg) = args.next() {
+                match arg {
+                    "--id" => id = Some(args.next()?.to_string()),
+                    _ if path.is_none() => path = Some(PathBuf::from(arg)),
+                    _ => return None,
+                }
+            }
+            Command::InstallLocal { path: path?, id }
+        }
+        "link" => {
+            let mut path = None;
+            let mut id = None;
+            let mut mode = LinkMode::Symlink;
+            let (mut scripts, mut watch) = (false, false);
+            while let Some(arg) = args.next() {
+                match arg {
+                    "--id" => id = Some(args.next()?.to_string()),
+                    "--copy" => mode = LinkMode::Copy,
+                    "--scripts" => scripts = true,
+                    "--watch" => watch = true,
+                    _ if path.is_none() => path = Some(PathBuf::from(arg)),
+                    _ => return None,
+                }
+            }
+            Command::Link {
+                path: path?,
+                id,
+                mode,
+                scripts,
+                watch,
+            }
+        }
+        "pack" => {
+            let mut options = PackOptions::default();
+            let mut path = None;
+            while let Some(arg) = args.next() {
+                match arg {
+                    "--id" => options.id = Some(args.next()?.to_string()),
+                    "--version" => options.version = Some(args.next()?.parse().ok()?),
+                    "--out" => options.out = Some(PathBuf::from(args.next()?)),
+                    "--url" => options.url = Some(args.next()?.to_string()),
+                    "--extract-to-root" => options.extract_to_root = true,
+                    "--dev" => options.dev_mod = true,
+                    _ if path.is_none() => path = Some(PathBuf::from(arg)),
+                    _ => return None,
+                }
+            }
+            options.path = path?;
+            Command::Pack(options)
+        }
+        "sign" => {
+            let mut path = None;
+            let mut key = None;
+            while let Some(arg) = args.next() {
+                match arg {
+                    "--key" => key = Some(PathBuf::from(args.next()?)),
+                    _ if path.is_none() => path = Some(PathBuf::from(arg)),
+                    _ => return None,
+                }
+            }
+            Command::Sign {
+                path: path?,
+                key: key?,
+            }
+        }
+        _ => return None,
+    };
+    match args.next() {
+        None => Some(command),
+        Some(_) => None,
+    }
+}
+
+/// Loads the state and the metadata of all games and mods and selects a game
+fn load_with_metadata() -> anyhow::Result<Manager> {
+    let mut manager = Manager::load()?;
+    manager.fetch_metadata();
+    manager.ensure_game_selected();
+    Ok(manager)
+}
+
+/// Logs the error of a failed action and returns the process exit code.
+fn exit_code(result: anyhow::Result<()>, action: &str) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(error) => {
+            log::error!("Failed to {}: {:#}", action, error);
+            -1
+        }
+    }
+}
+
+fn run(command: Command) -> i32 {
+    match command {
+        Command::Status => exit_code(
+            load_with_metadata().map(|manager| print_status(&manager)),
+            "show status",
+        ),
+        Command::Install(id) => exit_code(
+            load_with_metadata().and_then(|mut manager| manager.install_mod(&id)),
+            "install mod",
+        ),
+        Command::Update(id) => exit_code(
+            load_with_metadata().and_then(|mut manager| manager.update_mod(&id)),
+            "update mod",
+        ),
+        Command::Remove(id) => exit_code(
+            Manager::load().and_then(|mut manager| manager.remove_mod(&id)),
+            "remove mod",
+        ),
+        Command::Loader(action) => {
+            let result = Manager::load().and_then(|mut manager| {
+                manager.ensure_game_selected();
+                match action {
+                    LoaderAction::Install => manager.install_modloader(),
+                    LoaderAction::Remove => manager.remove_modloader(),
+                    LoaderAction::Enable => manager.set_modloader_enabled(true),
+                    LoaderAction::Disable => manager.set_modloader_enabled(false),
+                }
+            });
+            exit_code(result, "change mod loader")
+        }
+        Command::Launch(options) => {
+            match Manager::load().and_then(|mut manager| manager.launch_game_and_wait(options)) {
+                Ok(exit_code) => {
+                    match exit_code {
+                        Some(code) => log::info!("Game exited with code {}", code),
+                        None => log::info!("Game started"),
+                    }
+                    exit_code.unwrap_or(0)
+                }
+                Err(error) => {
+                    log::error!("Failed to launch game: {:#}", error);
+                    -1
+                }
+            }
+        }
+        Command::InstallLocal { path, id } => exit_code(
+            Manager::load().and_then(|mut manager| manager.install_local(&path, id)),
+            "install mod",
+        ),
+        Command::Link {
+            path,
+            id,
+            mode,
+            scripts,
+            watch,
+        } => {
+            let linked = Manager::load().and_then(|mut manager| {
+                let id = manager.link_mod(&path, id, mode, scripts)?;
+                Ok((manager, id))
+            });
+            let (mut manager, id) = match linked {
+                Ok(linked) => linked,
+                Err(error) => {
+                    log::error!("Failed to link mod: {:#}", error);
+                    return -1;
+                }
+            };
+            if watch {
+                log::info!(
+                    "Watching '{}' for changes of '{}'",
+                    path.to_string_lossy(),
+                    id
+                );
+                loop {
+                    std::thread::sleep(ytinu_core::dev_link::WATCH_INTERVAL);
+                    manager.redeploy_changed_links();
+                }
+            }
+            0
+        }
+        Command::Unlink(id) => exit_code(
+            Manager::load().and_then(|mut manager| manager.unlink_mod(&id)),
+            "unlink mod",
+        ),
+        Command::Pack(options) => match ytinu_core::pack::pack(&options) {
+            Ok(package) => {
+                log::info!(
+                    "Created '{}'. Add this entry to the metadata:",
+                    package.zip.to_string_lossy()
+                );
+                match package.entry_json() {
+                    Ok(json) => {
+                        println!("{}", json);
+                        0
+                    }
+                    Err(error) => {
+                        log::error!("Failed to serialize metadata entry: {}", error);
+                        -1
+                    }
+                }
+            }
+            Err(error) => {
+                log::error!("Failed to pack mod: {:#}", error);
+                -1
+            }
+        },
+        Command::LintMeta(dir) => {
+            let issues = ytinu_core::lint::lint_meta(&dir);
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            let errors = issues
+                .iter()
+                .filter(|issue| issue.severity == ytinu_core::lint::Severity::Error)
+                .count();
+            println!("{} errors, {} warnings", errors, issues.len() - errors);
+            if errors > 0 {
+                1
+            } else {
+                0
+            }
+        }
+        Command::Sign { path, key } => match sign_file(&path, &key) {
+            Ok(signature_path) => {
+                log::info!("Created '{}'", signature_path.to_string_lossy());
+                0
+            }
+            Err(error) => {
+                log::error!("Failed to sign file: {:#}", error);
+                -1
+            }
+        },
+    }
+}
+
+fn print_status(manager: &Manager) {
+    let state = manager.state();
+    if state.games.is_empty() {
+        println!("No game set up. Set up a game in the ytinu UI first.");
+        return;
+    }
+    let known_mods = manager.known_mods();
+    for (id, game) in &state.games {
+        let selected = state.selected_game.as_ref() == Some(id);
+        println!(
+            "{}{} ({})",
+            game.game.name,
+            if selected { " [selected]" } else { "" },
+            game.install_path
+        );
+        match &game.bep_in_ex {
+            Some(loader) => println!(
+                "  Mod loader: BepInEx {}{}",
+                loader
+                    .version
+                    .as_ref()
+                    .map_or_else(|| "(unknown version)".to_string(), |v| v.to_string()),
+                if loader.enabled { "" } else { " (disabled)" }
+            ),
+            None => println!("  Mod loader: not installed"),
+        }
+        let mut mods: Vec<_> = game.mods.values().collect();
+        mods.sort_by(|a, b| a.m.id.cmp(&b.m.id));
+        if mods.is_empty() && game.linked_mods.is_empty() {
+            println!("  No mods installed");
+        }
+        for installed in mods {
+            let update = known_mods
+                .get(&installed.m.id)
+                .filter(|m| selected && m.version > installed.version)
+                .map(|m| format!(" (update available: {})", m.version))
+                .unwrap_or_default();
+            println!(
+                "  {} {}{}{}",
+                installed.m.id,
+                installed.version,
+                if installed.m.local { " (local)" } else { "" },
+                update
+            );
+        }
+        let mut linked: Vec<_> = game.linked_mods.iter().collect();
+        linked.sort_by(|a, b| a.0.cmp(b.0));
+        for (id, link) in linked {
+            println!("  {} (linked to '{}')", id, link.source().to_string_lossy());
+        }
+    }
+}
+
+fn sign_file(path: &Path, key: &Path) -> anyhow::Result<PathBuf> {
+    let seed = std::fs::read_to_string(key)
+        .with_context(|| format!("Failed to read '{}'", key.to_string_lossy()))?;
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read '{}'", path.to_string_lossy()))?;
+    let (signature, public_key) = ytinu_core::signature::sign(&data, &seed)?;
+    if public_key != ytinu_core::signature::PUBLIC_KEY {
+        log::warn!(
+            "The key doesn't match the public key embedded in ytinu: {}",
+            public_key
+        );
+    }
+    let mut signature_path = path.as_os_str().to_owned();
+    signature_path.push(".sig");
+    let signature_path = PathBuf::from(signature_path);
+    std::fs::write(&signature_path, signature)
+        .with_context(|| format!("Failed to write '{}'", signature_path.to_string_lossy()))?;
+    Ok(signature_path)
+}
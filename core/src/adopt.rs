@@ -0,0 +1,136 @@
+//! Detection of mods that were installed manually into the plugins directory and registering
+//! them as mods managed by ytinu.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{InstalledMod, Mod, SetupGame},
+    fs::Fs,
+};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct UnmanagedFile {
+    /// Path relative to the plugins directory
+    pub path: String,
+    /// Known mod this file belongs to
+    pub matched_mod: Option<String>,
+    /// `true` if the file's checksum matches the download of the mod exactly
+    pub exact: bool,
+    /// Version read from the plugin metadata
+    pub version: Option<String>,
+}
+
+/// Lists all plugin files that don't belong to an installed mod.
+pub fn find_unmanaged(
+    fs: &dyn Fs,
+    game: &SetupGame,
+    known_mods: &HashMap<String, Mod>,
+) -> Vec<UnmanagedFile> {
+    // The hash in the metadata is the one of the download, so only mods that are downloaded as a
+    // single DLL can match a plugin file exactly
+    let by_hash: HashMap<&str, &Mod> = known_mods
+        .values()
+        .filter(|m| m.download.to_lowercase().ends_with(".dll"))
+        .filter_map(|m| Some((m.hash.as_deref()?, m)))
+        .collect();
+
+    crate::plugins::scan(fs, game, known_mods)
+        .files
+        .into_iter()
+        .filter(|file| file.mod_id.is_none())
+        .map(|file| {
+            let hash = crate::utils::checksum(fs, &game.plugins_path().join(&file.path)).ok();
+            let exact_match = hash
+                .as_deref()
+                .and_then(|hash| by_hash.get(hash))
+                .filter(|m| !game.mods.contains_key(&m.id));
+            let version = file
+                .assembly
+                .as_ref()
+                .and_then(|a| a.plugins.first())
+                .map(|p| p.version.clone());
+            UnmanagedFile {
+                exact: exact_match.is_some(),
+                version: match exact_match {
+                    Some(m) => Some(m.version.to_string()),
+                    None => version,
+                },
+                matched_mod: match exact_match {
+                    Some(m) => Some(m.id.clone()),
+                    None => file.matched_mod.filter(|id| !game.mods.contains_key(id)),
+                },
+                path: file.path,
+            }
+        })
+        .collect()
+}
+
+/// Registers the unmanaged files matched to `m` as installation of that mod.
+/// Returns `false` if none of the files belong to the mod.
+pub fn adopt(fs: &dyn Fs, game: &mut SetupGame, m: Mod, unmanaged: &[UnmanagedFile]) -> bool {
+    let matched: Vec<_> = unmanaged
+        .iter()
+        .filter(|file| file.matched_mod.as_ref() == Some(&m.id))
+        .collect();
+    if matched.is_empty() || game.mods.contains_key(&m.id) {
+        return false;
+    }
+
+    let matched_paths: Vec<PathBuf> = matched
+        .iter()
+        .map(|file| game.plugins_path().join(&file.path))
+        .collect();
+    let mut files = Vec::new();
+    for file in &matched {
+        let mut components = Path::new(&file.path).components();
+        let top_level = components.next().map(|c| c.as_os_str().to_string_lossy());
+        let path = match top_level {
+            // Adopt the whole directory of plugins that live in a subdirectory, including assets and
+            // configs, unless it also contains other plugins. Those would be removed with the mod.
+            Some(dir) if components.next().is_some() => {
+                let dlls = crate::plugins::find_dlls(fs, game.plugins_path().join(&*dir));
+                if dlls.iter().all(|dll| matched_paths.contains(dll)) {
+                    format!("BepInEx/plugins/{}", dir)
+                } else {
+                    format!("BepInEx/plugins/{}", file.path.replace('\\', "/"))
+                }
+            }
+            _ => format!("BepInEx/plugins/{}", file.path),
+        };
+        if !files.contains(&path) {
+            files.push(path);
+        }
+    }
+
+    let version = matched
+        .iter()
+        .find_map(|file| {
+            file.version
+                .as_deref()
+                .and_then(crate::plugins::parse_version)
+        })
+        .unwrap_or_else(|| m.version.clone());
+
+    log::info!(
+        "Adopting manually installed mod '{}' v{} ({})",
+        m.id,
+        version,
+        files.join(", ")
+    );
+    game.mods.insert(
+        m.id.clone(),
+        InstalledMod {
+            m,
+            version,
+            enabled: true,
+            files: Some(files),
+        },
+    );
+    true
+}
@@ -0,0 +1,1067 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, ensure, Context};
+use app_dirs::AppDataType;
+use schemars::JsonSchema;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    adopt::UnmanagedFile,
+    dev_link::LinkedMod,
+    dialog,
+    fetch::Fetcher,
+    fs::Fs,
+    launch::{LaunchRecord, MAX_LAUNCH_HISTORY},
+    manager::{BEP_IN_EX_DOWNLOAD_URL, BEP_IN_EX_FILE_NAME, BEP_IN_EX_VERSION, GAME_MODS_URL_BASE},
+    migrations::CONFIG_MIGRATIONS,
+    persist::{JsonFile, Loaded},
+    plugin_config::{ConfigEntryUpdate, PluginConfig, PluginConfigInfo},
+    utils::hash,
+    Env, ErrorExt,
+};
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct State {
+    pub selected_game: Option<String>,
+    pub games: HashMap<String, SetupGame>,
+    pub shown_messages: HashSet<String>,
+}
+
+impl State {
+    pub fn current_game(&self) -> Option<&SetupGame> {
+        self.games.get(self.selected_game.as_ref()?)
+    }
+
+    pub fn current_game_mut(&mut self) -> Option<&mut SetupGame> {
+        self.games.get_mut(self.selected_game.as_ref()?)
+    }
+}
+
+/// Failures that front-ends may want to handle specially, e.g. with a specific error code.
+/// Returned wrapped in an `anyhow::Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionError {
+    /// The game is running and the user chose not to modify its files
+    GameRunning,
+    /// The user cancelled the action in a confirmation dialog
+    Cancelled,
+    NoGameSelected,
+    /// A mod, game or file doesn't exist
+    NotFound(String),
+    /// The action conflicts with the current state, e.g. the mod is already installed
+    Conflict(String),
+    /// An argument of the action is invalid, e.g. a path that doesn't exist
+    InvalidInput(String),
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::GameRunning => write!(f, "The game is running"),
+            ActionError::Cancelled => write!(f, "Cancelled"),
+            ActionError::NoGameSelected => write!(f, "No game set up or selected"),
+            ActionError::NotFound(message)
+            | ActionError::Conflict(message)
+            | ActionError::InvalidInput(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[schemars(rename = "State")]
+pub struct StateOut {
+    #[schemars(with = "String")]
+    version: semver::Version,
+    selected_game: Option<String>,
+    games: HashMap<String, SetupGame>,
+    os: &'static str,
+    running_games: Vec<String>,
+    /// Plugin files of the selected game that aren't managed by ytinu
+    unmanaged: Vec<UnmanagedFile>,
+}
+
+impl StateOut {
+    pub fn new(state: &State, running_games: Vec<String>, unmanaged: Vec<UnmanagedFile>) -> Self {
+        Self {
+            version: crate::APP_VERSION.clone(),
+            selected_game: state.selected_game.clone(),
+            games: state.games.clone(),
+            os: std::env::consts::OS,
+            running_games,
+            unmanaged,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct SetupGame {
+    pub game: Game,
+    pub install_path: String,
+    pub mods: HashMap<String, InstalledMod>,
+    pub bep_in_ex: Option<BepInExInfo>,
+    #[serde(default)]
+    pub launches: Vec<LaunchRecord>,
+    /// Manually installed mods the user doesn't want ytinu to manage
+    #[serde(default)]
+    pub declined_adoptions: HashSet<String>,
+    /// Mods deployed from a local build directory
+    #[serde(default)]
+    pub linked_mods: HashMap<String, LinkedMod>,
+}
+
+impl SetupGame {
+    pub fn install_path(&self) -> &Path {
+        Path::new(&self.install_path)
+    }
+
+    pub fn plugins_path(&self) -> PathBuf {
+        self.install_path().join("BepInEx").join("plugins")
+    }
+
+    /// Directories that can be opened from the UI: `game`, `mods` and `config`
+    pub fn dir_path(&self, dir: &str) -> Option<PathBuf> {
+        match dir {
+            "game" => Some(self.install_path().to_path_buf()),
+            "mods" => Some(self.plugins_path()),
+            "config" => Some(self.config_path()),
+            _ => None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        crate::process::is_running_in(self.install_path())
+    }
+
+    /// Checks whether the game is running and if so, asks the user whether to modify its files anyway.
+    /// Returns `true` if it's safe or the user chose to continue.
+    fn confirm_not_running(&self) -> bool {
+        let processes = crate::process::find_processes_in(self.install_path());
+        if processes.is_empty() {
+            return true;
+        }
+        for process in &processes {
+            log::warn!(
+                "{} is running: pid {} ('{}')",
+                self.game.name,
+                process.pid,
+                process.exe.to_string_lossy()
+            );
+        }
+        dialog::confirm(
+            "Game is running",
+            &format!(
+                "{} is currently running.\n\
+                 Changing mods or the Mod Loader while the game is running can fail or leave it in a broken state.\n\
+                 Please close the game first.\n\
+                 \n\
+                 Do you want to continue anyway?",
+                self.game.name
+            ),
+            MessageIcon::Warning,
+            false,
+        )
+    }
+
+    pub(crate) fn ensure_not_running(&self) -> anyhow::Result<()> {
+        ensure!(self.confirm_not_running(), ActionError::GameRunning);
+        Ok(())
+    }
+
+    pub fn record_launch(&mut self, record: LaunchRecord) {
+        self.launches.push(record);
+        if self.launches.len() > MAX_LAUNCH_HISTORY {
+            let excess = self.launches.len() - MAX_LAUNCH_HISTORY;
+            self.launches.drain(..excess);
+        }
+    }
+
+    /// Files and directories that belong to an installed mod
+    pub fn mod_paths(&self, id: &str) -> Vec<PathBuf> {
+        match self.mods.get(id).and_then(InstalledMod::files) {
+            Some(files) => files.iter().map(|f| self.install_path().join(f)).collect(),
+            None => vec![
+                self.plugins_path().join(id),
+                self.plugins_path().join(format!("{}.dll", id)),
+            ],
+        }
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.install_path().join("BepInEx").join("config")
+    }
+
+    pub fn list_plugin_configs(&self, env: &Env) -> anyhow::Result<Vec<PluginConfigInfo>> {
+        let dir = self.config_path();
+        if !env.fs.is_dir(&dir) {
+            return Ok(Vec::new());
+        }
+        let mut configs = Vec::new();
+        for path in env
+            .fs
+            .read_dir(&dir)
+            .context("Failed to list config files")?
+        {
+            if path.extension().is_some_and(|ext| ext == "cfg") && env.fs.is_file(&path) {
+                let (plugin, guid) = PluginConfig::load(&*env.fs, &path)?.plugin_info();
+                configs.push(PluginConfigInfo {
+                    file: path.file_name().unwrap().to_string_lossy().to_string(),
+                    plugin,
+                    guid,
+                });
+            }
+        }
+        configs.sort_by(|a, b| a.file.cmp(&b.file));
+        Ok(configs)
+    }
+
+    pub fn plugin_config(&self, env: &Env, file: &str) -> anyhow::Result<PluginConfig> {
+        crate::plugin_config::validate_file_name(file)?;
+        PluginConfig::load(&*env.fs, &self.config_path().join(file))
+    }
+
+    pub fn update_plugin_config(
+        &self,
+        env: &Env,
+        file: &str,
+        update: ConfigEntryUpdate,
+    ) -> anyhow::Result<PluginConfig> {
+        let mut config = self.plugin_config(env, file)?;
+        config.update(update)?;
+        self.ensure_not_running()?;
+        config.store(&*env.fs, &self.config_path().join(file))?;
+        Ok(config)
+    }
+
+    pub fn update_mods_meta(&mut self, new_mods: &HashMap<String, Mod>) {
+        for (id, m) in self.mods.iter_mut().filter(|(_, m)| !m.m.local) {
+            if let Some(new_m) = new_mods.get(id) {
+                m.m = new_m.clone();
+            }
+        }
+    }
+
+    pub fn install_mod(&mut self, env: &Env, m: Mod) -> anyhow::Result<()> {
+        self.ensure_not_running()?;
+        self.replace_or_install_mod_impl(env, m)
+    }
+
+    /// Installs a mod. A locally installed build of the mod is upgraded to the new version and
+    /// a local build always replaces the installed version.
+    fn replace_or_install_mod_impl(&mut self, env: &Env, m: Mod) -> anyhow::Result<()> {
+        if let Some(installed) = self.mods.get(&m.id) {
+            ensure!(installed.m.local || m.local, "Mod already installed");
+            log::info!(
+                "Replacing '{}' v{}{} with v{}{}",
+                m.id,
+                installed.version,
+                if installed.m.local { " (local)" } else { "" },
+                m.version,
+                if m.local { " (local)" } else { "" },
+            );
+            self.remove_mod_impl(env, &m.id)?;
+        }
+        self.install_mod_impl(env, m)
+    }
+
+    pub fn install_mod_impl(&mut self, env: &Env, m: Mod) -> anyhow::Result<()> {
+        ensure!(!self.mods.contains_key(&m.id), "Mod already installed");
+        ensure!(
+            !self.linked_mods.contains_key(&m.id),
+            "Mod is linked to a local build directory"
+        );
+
+        let url = reqwest::Url::parse(&m.download)
+            .with_context(|| format!("Invalid download url: '{}'", m.download))?;
+        let file = url
+            .path_segments()
+            .and_then(|mut ps| ps.next_back())
+            .with_context(|| format!("Invalid download url (no file): '{}'", m.download))?;
+
+        let local_file = if m.local {
+            let path = url
+                .to_file_path()
+                .map_err(|()| anyhow!("Invalid local file url: '{}'", m.download))?;
+            ensure!(
+                env.fs.is_file(&path),
+                "File not found: '{}'",
+                path.to_string_lossy()
+            );
+            Some(path)
+        } else {
+            None
+        };
+
+        if file.ends_with(".dll") {
+            let target = self.plugins_path().join(format!("{}.dll", m.id));
+            if let Some(path) = &local_file {
+                crate::utils::create_parent_dirs(&*env.fs, &target)?;
+                env.fs.copy(path, &target).with_context(|| {
+                    format!("Failed to copy mod from '{}'", path.to_string_lossy())
+                })?;
+            } else {
+                crate::utils::download(env, &m.download, &target)
+                    .context("Failed to download mod")?;
+            }
+        } else if file.ends_with(".zip") {
+            // The version is part of the name so updates don't extract a cached older version
+            let fname = format!("{}_{}_{}.zip", self.game.id, m.id, m.version);
+            let target_dir = if m.extract_to_root {
+                self.install_path().to_path_buf()
+            } else {
+                self.plugins_path().join(&m.id)
+            };
+            if let Some(path) = &local_file {
+                crate::utils::unzip(&*env.fs, path, &target_dir)?;
+            } else {
+                crate::utils::download_cached_and_unzip(
+                    env,
+                    &m.download,
+                    &fname,
+                    &self.plugins_path().join(&fname),
+                    &target_dir,
+                )?;
+            }
+        } else {
+            bail!(
+                "Unrecognized file type in download URL: {}\nValid types are only .zip and .dll",
+                m.download
+            );
+        }
+
+        self.mods.insert(
+            m.id.clone(),
+            InstalledMod {
+                version: m.version.clone(),
+                enabled: true,
+                m,
+                files: None,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn update_mod(&mut self, env: &Env, mod_id: &str) -> anyhow::Result<()> {
+        self.ensure_not_running()?;
+        let m = self.remove_mod_impl(env, mod_id)?;
+        self.install_mod_impl(env, m)
+    }
+
+    pub fn remove_mod(&mut self, env: &Env, mod_id: &str) -> anyhow::Result<()> {
+        self.ensure_not_running()?;
+        self.remove_mod_impl(env, mod_id).map(|_| ())
+    }
+
+    fn remove_mod_impl(&mut self, env: &Env, mod_id: &str) -> anyhow::Result<Mod> {
+        let m = self.mods.get(mod_id).context("Mod is not installed")?;
+
+        if let Some(files) = m.files() {
+            for file in files {
+                let path = self.install_path().join(file);
+                crate::utils::remove_file_or_dir(&*env.fs, path)
+                    .with_context(|| format!("Failed to remove '{}'", file))?;
+            }
+        } else {
+            let paths = env
+                .fs
+                .read_dir(&self.plugins_path())
+                .context("Failed to list files in plugins directory")?;
+            for path in paths {
+                let fname = path.file_name().unwrap_or_default().to_string_lossy();
+                if fname.starts_with(mod_id) {
+                    crate::utils::remove_file_or_dir(&*env.fs, &path)
+                        .with_context(|| format!("Failed to remove '{}'", fname))?;
+                }
+            }
+        }
+
+        Ok(self.mods.remove(mod_id).unwrap().m)
+    }
+
+    pub fn install_modloader(&mut self, env: &Env) -> anyhow::Result<()> {
+        ensure!(
+            self.bep_in_ex.is_none(),
+            "The Mod Loader is already installed"
+        );
+        self.ensure_not_running()?;
+        let target = self.install_path().join(BEP_IN_EX_FILE_NAME);
+        crate::utils::download_cached_and_unzip(
+            env,
+            BEP_IN_EX_DOWNLOAD_URL,
+            BEP_IN_EX_FILE_NAME,
+            &target,
+            self.install_path(),
+        )?;
+
+        self.bep_in_ex = Some(BepInExInfo {
+            enabled: true,
+            version: Some(BEP_IN_EX_VERSION.clone()),
+            hash: env
+                .fs
+                .read(
+                    &self
+                        .install_path()
+                        .join("BepInEx")
+                        .join("core")
+                        .join("BepInEx.dll"),
+                )
+                .ok()
+                .map(|data| hash(&data)),
+        });
+        Ok(())
+    }
+
+    /// Removes the Mod Loader including all mods and configuration after asking the user.
+    pub fn remove_modloader(&mut self, env: &Env) -> anyhow::Result<()> {
+        ensure!(self.bep_in_ex.is_some(), "The Mod Loader is not installed");
+        self.ensure_not_running()?;
+        let confirmed = dialog::confirm(
+            "Are you sure?",
+            "Are you sure?\n\
+             This will remove all mods and all stored configuration.\n\
+             You can disable the Mod Loader instead if you just want to start the games without loading any mods.",
+            MessageIcon::Question,
+            false,
+        );
+        ensure!(confirmed, ActionError::Cancelled);
+
+        let path = self.install_path().join("BepInEx");
+        if env.fs.is_dir(&path) {
+            env.fs
+                .remove_dir_all(&path)
+                .context("Failed to remove BepInEx directory")?;
+        }
+        // Forget the mods once their files are gone even if removing the rest fails
+        self.mods.clear();
+        self.linked_mods.clear();
+        let path = self.install_path().join("doorstop_config.ini");
+        if env.fs.is_file(&path) {
+            env.fs
+                .remove_file(&path)
+                .context("Failed to remove doorstop_config.ini")?;
+        }
+        let path = self.install_path().join("winhttp.dll");
+        if env.fs.is_file(&path) {
+            env.fs
+                .remove_file(&path)
+                .context("Failed to remove winhttp.dll")?;
+        }
+        self.bep_in_ex = None;
+        Ok(())
+    }
+
+    pub fn set_modloader_enabled(&mut self, env: &Env, enabled: bool) -> anyhow::Result<()> {
+        let is_enabled = self
+            .bep_in_ex
+            .as_ref()
+            .context("BepInEx is not installed for this game")?
+            .enabled;
+        if is_enabled == enabled {
+            return Ok(());
+        }
+        self.ensure_not_running()?;
+
+        let install_path = self.install_path();
+        let ini = install_path.join("doorstop_config.ini");
+        let ini_target = install_path.join("BepInEx").join("doorstop_config.ini");
+        let dll = install_path.join("winhttp.dll");
+        let dll_target = install_path.join("BepInEx").join("winhttp.dll");
+        let moves = if enabled {
+            [(ini_target, ini), (dll_target, dll)]
+        } else {
+            [(ini, ini_target), (dll, dll_target)]
+        };
+        for (from, to) in &moves {
+            env.fs.rename(from, to).with_context(|| {
+                format!(
+                    "Failed to move '{}'",
+                    from.file_name().unwrap().to_string_lossy()
+                )
+            })?;
+        }
+        self.bep_in_ex.as_mut().unwrap().enabled = enabled;
+        Ok(())
+    }
+
+    pub fn update_modloader_status(&mut self, env: &Env) {
+        let install_path = Path::new(&self.install_path);
+        let bep_in_ex_path = install_path.join("BepInEx");
+        let dll_path = bep_in_ex_path.join("core").join("BepInEx.dll");
+        let checksum = || env.fs.read(&dll_path).map(|data| hash(&data));
+
+        if env.fs.exists(&dll_path) {
+            if let Some(bep_in_ex) = self.bep_in_ex.as_mut() {
+                if let Some(expected_hash) = bep_in_ex.hash.as_ref() {
+                    if let Ok(new_hash) = checksum() {
+                        if &new_hash != expected_hash {
+                            bep_in_ex.version = None;
+                            bep_in_ex.hash = Some(new_hash);
+                        }
+                    }
+                }
+                let enabled = self.check_bep_in_ex_enabled(env);
+                self.bep_in_ex.as_mut().unwrap().enabled = enabled;
+            } else {
+                let remove = dialog::confirm(
+                    "BepInEx detected",
+                    "An unknown existing installation of the BepInEx ModLoader was detected. Do you want to remove it?",
+                    MessageIcon::Question,
+                    true,
+                );
+                if !remove || !self.confirm_not_running() {
+                    let bep_in_ex = BepInExInfo {
+                        version: None,
+                        enabled: self.check_bep_in_ex_enabled(env),
+                        hash: checksum().ok(),
+                    };
+                    self.bep_in_ex = Some(bep_in_ex);
+                    return;
+                }
+
+                let keep = dialog::confirm(
+                    "Removing BepInEx",
+                    "Do you want to keep configs and mods?",
+                    MessageIcon::Question,
+                    true,
+                );
+                if keep {
+                    env.fs
+                        .remove_dir_all(&bep_in_ex_path.join("core"))
+                        .unwrap_or_msg("Failed to remove 'core' directory");
+                    env.fs
+                        .remove_dir_all(&bep_in_ex_path.join("cache"))
+                        .unwrap_or_msg("Failed to remove 'cache' directory");
+                } else {
+                    env.fs
+                        .remove_dir_all(&bep_in_ex_path)
+                        .unwrap_or_msg("Failed to remove 'BepInEx' directory");
+                }
+            }
+        } else {
+            self.bep_in_ex = None;
+        }
+    }
+
+    fn check_bep_in_ex_enabled(&self, env: &Env) -> bool {
+        let install_path = Path::new(&self.install_path);
+        env.fs.exists(&install_path.join("doorstop_config.ini"))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct BepInExInfo {
+    #[schemars(with = "Option<String>")]
+    pub version: Option<semver::Version>,
+    pub enabled: bool,
+    hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct InstalledMod {
+    pub m: Mod,
+    #[schemars(with = "String")]
+    pub version: Version,
+    pub enabled: bool,
+    /// Files that were actually installed, relative to the install path.
+    /// Only set if they differ from `m.files`, e.g. for adopted mods.
+    #[serde(default)]
+    pub files: Option<Vec<String>>,
+}
+
+impl InstalledMod {
+    pub fn files(&self) -> Option<&Vec<String>> {
+        self.files.as_ref().or(self.m.files.as_ref())
+    }
+}
+
+/// Newest metadata format this version of ytinu understands
+pub static METADATA_FORMAT: u32 = 2;
+pub static STABLE_CHANNEL: &str = "stable";
+pub static BETA_CHANNEL: &str = "beta";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Metadata {
+    pub format: u32,
+    /// ytinu releases by update channel
+    pub releases: HashMap<String, AppRelease>,
+    pub messages: Vec<Message>,
+    pub games: HashMap<String, Game>,
+    pub game_mods: HashMap<String, HashMap<String, Mod>>,
+    pub mods: HashMap<String, Mod>,
+    pub changelog: Vec<ReleaseNotes>,
+    /// Invalid entries that were skipped while parsing the metadata
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+impl Metadata {
+    pub fn release(&self, channel: &str) -> Option<&AppRelease> {
+        self.releases.get(channel)
+    }
+
+    /// Newest release available on an update channel. The beta channel also receives stable
+    /// releases, the stable channel never receives pre-releases.
+    pub fn latest_release(&self, channel: UpdateChannel) -> Option<&AppRelease> {
+        channel
+            .channels()
+            .iter()
+            .filter_map(|name| self.release(name))
+            .filter(|release| channel == UpdateChannel::Beta || release.version.pre.is_empty())
+            .max_by(|a, b| a.version.cmp(&b.version))
+    }
+
+    /// Release notes of all versions after `installed` up to `release`, newest first
+    pub fn release_notes(&self, installed: &Version, release: &AppRelease) -> Vec<ReleaseNotes> {
+        let mut notes: Vec<ReleaseNotes> = self
+            .changelog
+            .iter()
+            .filter(|entry| &entry.version > installed && entry.version <= release.version)
+            .cloned()
+            .collect();
+        if let Some(release_notes) = &release.notes {
+            if !notes.iter().any(|entry| entry.version == release.version) {
+                notes.push(ReleaseNotes {
+                    version: release.version.clone(),
+                    notes: release_notes.clone(),
+                });
+            }
+        }
+        notes.sort_by(|a, b| b.version.cmp(&a.version));
+        notes
+    }
+}
+
+impl From<MetadataIn> for Metadata {
+    fn from(meta: MetadataIn) -> Self {
+        let warnings = meta.warnings();
+        Self {
+            format: meta.format,
+            releases: meta.releases(),
+            messages: meta.messages.entries,
+            games: meta
+                .games
+                .entries
+                .into_iter()
+                .map(|g| (g.id.clone(), g))
+                .collect(),
+            mods: meta
+                .mods
+                .entries
+                .into_iter()
+                .map(|m| (m.id.clone(), m))
+                .collect(),
+            game_mods: HashMap::new(),
+            changelog: meta.changelog.entries,
+            warnings,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+#[schemars(rename = "Metadata")]
+pub struct MetadataOut {
+    format: u32,
+    /// Latest ytinu version on the configured update channel
+    #[schemars(with = "Option<String>")]
+    version: Option<semver::Version>,
+    /// Whether the latest version is newer than the running one and not skipped
+    update: bool,
+    releases: HashMap<String, AppRelease>,
+    games: HashMap<String, Game>,
+    game_mods: HashMap<String, HashMap<String, Mod>>,
+    mods: HashMap<String, Mod>,
+    warnings: Vec<String>,
+}
+
+impl MetadataOut {
+    pub fn new(meta: &Metadata, latest: Option<&AppRelease>, update: bool) -> Self {
+        Self {
+            format: meta.format,
+            version: latest.map(|release| release.version.clone()),
+            update,
+            releases: meta.releases.clone(),
+            games: meta.games.clone(),
+            game_mods: meta.game_mods.clone(),
+            mods: meta.mods.clone(),
+            warnings: meta.warnings.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MetadataIn {
+    /// Format version of the file. Files without it use format 1.
+    #[serde(default = "MetadataIn::legacy_format")]
+    pub format: u32,
+    /// Latest stable ytinu version. Replaced by `releases` in format 2 but still read by older
+    /// ytinu versions.
+    #[serde(default)]
+    pub version: Option<semver::Version>,
+    /// Download URLs of the latest stable version by OS. Replaced by `releases` in format 2.
+    #[serde(default)]
+    pub downloads: HashMap<String, String>,
+    /// ytinu releases by update channel
+    #[serde(default)]
+    pub releases: LenientMap<AppRelease>,
+    /// Release notes of older ytinu versions
+    #[serde(default)]
+    pub changelog: Lenient<ReleaseNotes>,
+    pub messages: Lenient<Message>,
+    pub games: Lenient<Game>,
+    pub mods: Lenient<Mod>,
+}
+
+impl MetadataIn {
+    fn legacy_format() -> u32 {
+        1
+    }
+
+    /// Releases by channel, using the format 1 fields for the stable channel if necessary
+    pub fn releases(&self) -> HashMap<String, AppRelease> {
+        let mut releases = self.releases.entries.clone();
+        if let (Some(version), false) = (&self.version, releases.contains_key(STABLE_CHANNEL)) {
+            releases.insert(
+                STABLE_CHANNEL.to_string(),
+                AppRelease {
+                    version: version.clone(),
+                    downloads: self.downloads.clone(),
+                    hashes: HashMap::new(),
+                    notes: None,
+                },
+            );
+        }
+        releases
+    }
+
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.format > METADATA_FORMAT {
+            warnings.push(format!(
+                "The metadata uses format {} but this version of ytinu only supports format {}. \
+                 Some information may be missing, please update ytinu.",
+                self.format, METADATA_FORMAT
+            ));
+        }
+        if !self.releases().contains_key(STABLE_CHANNEL) {
+            warnings.push("The metadata contains no stable ytinu release".to_string());
+        }
+        warnings.extend(self.releases.warnings("release"));
+        warnings.extend(self.changelog.warnings("changelog entry"));
+        warnings.extend(self.messages.warnings("message"));
+        warnings.extend(self.games.warnings("game"));
+        warnings.extend(self.mods.warnings("mod"));
+        warnings
+    }
+}
+
+/// ytinu release of an update channel
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct AppRelease {
+    #[schemars(with = "String")]
+    pub version: semver::Version,
+    /// Download URL by OS (`windows`, `linux`, `macos`)
+    pub downloads: HashMap<String, String>,
+    /// blake2s checksum of the download by OS
+    #[serde(default)]
+    pub hashes: HashMap<String, String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ReleaseNotes {
+    #[schemars(with = "String")]
+    pub version: semver::Version,
+    pub notes: String,
+}
+
+/// Available ytinu update for the configured channel
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct UpdateInfo {
+    #[schemars(with = "String")]
+    pub installed: semver::Version,
+    pub channel: UpdateChannel,
+    pub release: Option<AppRelease>,
+    /// The user chose to skip this release
+    pub skipped: bool,
+    /// Release notes of all versions between the installed and the available release
+    pub notes: Vec<ReleaseNotes>,
+}
+
+/// List that skips entries which fail to deserialize instead of failing as a whole
+#[derive(Debug, Clone)]
+pub struct Lenient<T> {
+    pub entries: Vec<T>,
+    /// Id (if any) and error of skipped entries
+    pub skipped: Vec<(Option<String>, String)>,
+}
+
+impl<T> Lenient<T> {
+    pub fn warnings(&self, kind: &str) -> Vec<String> {
+        skipped_warnings(kind, &self.skipped)
+    }
+}
+
+impl<T> Default for Lenient<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+}
+
+impl<'de, T: serde::de::DeserializeOwned> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut lenient = Lenient::default();
+        for value in Vec::<serde_json::Value>::deserialize(deserializer)? {
+            let id = value.get("id").and_then(|id| id.as_str()).map(String::from);
+            match serde_json::from_value(value) {
+                Ok(entry) => lenient.entries.push(entry),
+                Err(error) => lenient.skipped.push((id, error.to_string())),
+            }
+        }
+        Ok(lenient)
+    }
+}
+
+/// Map that skips entries which fail to deserialize instead of failing as a whole
+#[derive(Debug, Clone)]
+pub struct LenientMap<T> {
+    pub entries: HashMap<String, T>,
+    /// Key and error of skipped entries
+    pub skipped: Vec<(Option<String>, String)>,
+}
+
+impl<T> LenientMap<T> {
+    pub fn warnings(&self, kind: &str) -> Vec<String> {
+        skipped_warnings(kind, &self.skipped)
+    }
+}
+
+impl<T> Default for LenientMap<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            skipped: Vec::new(),
+        }
+    }
+}
+
+impl<'de, T: serde::de::DeserializeOwned> Deserialize<'de> for LenientMap<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut lenient = LenientMap::default();
+        for (key, value) in HashMap::<String, serde_json::Value>::deserialize(deserializer)? {
+            match serde_json::from_value(value) {
+                Ok(entry) => {
+                    lenient.entries.insert(key, entry);
+                }
+                Err(error) => lenient.skipped.push((Some(key), error.to_string())),
+            }
+        }
+        Ok(lenient)
+    }
+}
+
+fn skipped_warnings(kind: &str, skipped: &[(Option<String>, String)]) -> Vec<String> {
+    skipped
+        .iter()
+        .map(|(id, error)| match id {
+            Some(id) => format!("Skipped invalid {} '{}': {}", kind, id, error),
+            None => format!("Skipped invalid {}: {}", kind, error),
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Message {
+    pub id: String,
+    #[serde(default = "semver::VersionReq::any")]
+    pub version: semver::VersionReq,
+    pub message: String,
+    #[serde(default)]
+    pub icon: MessageIcon,
+    #[serde(default)]
+    pub show_always: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MessageIcon {
+    #[default]
+    Info,
+    Question,
+    Error,
+    Warning,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Game {
+    pub id: String,
+    pub name: String,
+    pub appid: Option<String>,
+    /// Path of the game executable relative to the installation directory
+    #[serde(default)]
+    pub executable: Option<String>,
+    pub recommended_mods: Vec<String>,
+}
+
+impl Game {
+    pub fn fetch_mods(&self, fetcher: &dyn Fetcher) -> Option<GameMods> {
+        let url = format!("{}/{}.json", GAME_MODS_URL_BASE, self.id);
+        crate::signature::fetch_signed_json(fetcher, &url, "game mod list")
+    }
+
+    pub fn find_installation_dir(&self, fs: &dyn Fs) -> Option<String> {
+        let path = if cfg!(windows) {
+            Path::new(r"C:\Program Files (x86)\Steam\steamapps\common").join(&self.name)
+        } else {
+            Path::new("~/.steam/steam/SteamApps/common").join(&self.name)
+        };
+        if fs.is_dir(&path) {
+            Some(path.to_string_lossy().to_string())
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Mod {
+    pub id: String,
+    pub name: String,
+    pub download: String,
+    #[schemars(with = "String")]
+    pub version: semver::Version,
+    #[serde(default)]
+    pub extract_to_root: bool,
+    pub files: Option<Vec<String>>,
+    /// blake2s checksum of the downloaded file
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// Size of the downloaded file in bytes
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Installed from a local file instead of a metadata repository
+    #[serde(default)]
+    pub local: bool,
+    #[serde(default)]
+    pub dev_mod: bool,
+    pub source: Option<String>,
+    pub homepage: Option<String>,
+    pub description: Option<String>,
+    #[schemars(with = "Option<String>")]
+    pub ytinu_version: Option<semver::VersionReq>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GameMods {
+    pub mods: Lenient<Mod>,
+}
+
+impl From<GameMods> for HashMap<String, Mod> {
+    fn from(game_mods: GameMods) -> Self {
+        game_mods
+            .mods
+            .entries
+            .into_iter()
+            .map(|m| (m.id.clone(), m))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub dark_mode: DarkMode,
+    pub show_dev_mods: bool,
+    pub port: u16,
+    pub check_for_updates: bool,
+    pub update_channel: UpdateChannel,
+    /// Release the user doesn't want to be notified about
+    #[schemars(with = "Option<String>")]
+    pub skipped_version: Option<semver::Version>,
+    pub open_ui: OpenUIConfig,
+}
+
+impl Config {
+    /// Fails if the config file exists but can't be used, so it isn't overwritten.
+    pub fn load() -> anyhow::Result<Self> {
+        Ok(match Self::file()?.load() {
+            Loaded::Ok(config) => config,
+            Loaded::Missing => Self::default(),
+            Loaded::Recovered { value, .. } => value,
+            Loaded::Unusable(error) => return Err(error.context("The config file can't be used")),
+            Loaded::Failed(error) => {
+                crate::show_error(&format!("Failed to load config file: {:#}", error));
+                Self::default()
+            }
+        })
+    }
+
+    fn file() -> anyhow::Result<JsonFile> {
+        let path = crate::utils::app_dir(AppDataType::UserConfig)
+            .context("Failed to get config directory")?
+            .join("config.json");
+        Ok(JsonFile::new(path, 0, CONFIG_MIGRATIONS))
+    }
+
+    pub fn store(&self) {
+        if let Err(error) = Self::file().and_then(|file| file.store(self)) {
+            crate::show_error(&format!("Failed to save config file: {:#}", error));
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            dark_mode: DarkMode::System,
+            show_dev_mods: false,
+            port: 0,
+            check_for_updates: true,
+            update_channel: UpdateChannel::Stable,
+            skipped_version: None,
+            open_ui: OpenUIConfig::Chromium,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DarkMode {
+    System,
+    Dark,
+    Light,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    /// Release channels in the metadata that are considered for updates
+    pub fn channels(self) -> Vec<&'static str> {
+        match self {
+            UpdateChannel::Stable => vec![STABLE_CHANNEL],
+            UpdateChannel::Beta => vec![STABLE_CHANNEL, BETA_CHANNEL],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenUIConfig {
+    Chromium,
+    Browser,
+    None,
+}
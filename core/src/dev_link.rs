@@ -0,0 +1,259 @@
+//! Developer link mode: deploys a mod from a local build output directory and redeploys it
+//! whenever the build output changes.
+//!
+//! Linked mods are either deployed to `BepInEx/plugins/<id>` or, for hot reloading with
+//! BepInEx ScriptEngine, to `BepInEx/scripts`. ScriptEngine loads the assemblies from memory so
+//! the files aren't locked while the game is running and can be replaced by a new build.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{ensure, Context};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{data::ActionError, fs::RealFs};
+
+pub static WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// File types deployed to the ScriptEngine `scripts` directory
+static SCRIPT_EXTENSIONS: &[&str] = &["dll", "pdb", "mdb"];
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+    Symlink,
+    Copy,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct LinkedMod {
+    /// Build output directory of the mod
+    pub source: String,
+    pub mode: LinkMode,
+    /// Deploy to `BepInEx/scripts` instead of `BepInEx/plugins`
+    pub scripts: bool,
+    /// Deployed files and directories, relative to the install path
+    #[serde(default)]
+    pub deployed: Vec<String>,
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct LinkRequest {
+    pub path: String,
+    /// Detected from the plugin assemblies in the directory if not set
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub copy: bool,
+    #[serde(default)]
+    pub scripts: bool,
+}
+
+/// Modification times and sizes of all files in a build output directory
+pub type Snapshot = HashMap<PathBuf, (Option<SystemTime>, u64)>;
+
+impl LinkedMod {
+    pub fn new(source: &Path, mode: LinkMode, scripts: bool) -> anyhow::Result<Self> {
+        ensure!(
+            source.is_dir(),
+            "Directory not found: '{}'",
+            source.to_string_lossy()
+        );
+        let source = source
+            .canonicalize()
+            .with_context(|| format!("Invalid path: '{}'", source.to_string_lossy()))?;
+        Ok(Self {
+            source: source.to_string_lossy().to_string(),
+            mode,
+            scripts,
+            deployed: Vec::new(),
+        })
+    }
+
+    pub fn source(&self) -> &Path {
+        Path::new(&self.source)
+    }
+
+    /// Whether changes in the source directory have to be deployed again.
+    /// A symlinked plugin directory always shows the current build output.
+    pub fn needs_watching(&self) -> bool {
+        self.mode == LinkMode::Copy || self.scripts
+    }
+
+    /// Removes previously deployed files and deploys the current build output.
+    /// Fails if a target exists that wasn't deployed by this link and leaves it untouched.
+    pub fn deploy(&mut self, install_path: &Path, id: &str) -> anyhow::Result<()> {
+        self.undeploy(install_path)?;
+
+        // Source and target relative to the install path
+        let mut targets = Vec::new();
+        if self.scripts {
+            let entries = self
+                .source()
+                .read_dir()
+                .with_context(|| format!("Failed to list files in '{}'", self.source))?;
+            for entry in entries {
+                let path = entry
+                    .with_context(|| format!("Failed to list files in '{}'", self.source))?
+                    .path();
+                let is_script = path.extension().is_some_and(|ext| {
+                    SCRIPT_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str())
+                });
+                if !path.is_file() || !is_script {
+                    continue;
+                }
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                targets.push((path, format!("BepInEx/scripts/{}", name)));
+            }
+        } else {
+            targets.push((
+                self.source().to_path_buf(),
+                format!("BepInEx/plugins/{}", id),
+            ));
+        }
+        // Everything deployed before was removed above, so existing files belong to the user
+        if let Some((_, target)) = targets
+            .iter()
+            .find(|(_, target)| std::fs::symlink_metadata(install_path.join(target)).is_ok())
+        {
+            return Err(ActionError::Conflict(format!(
+                "'{}' already exists. Remove it before linking '{}'.",
+                target, id
+            ))
+            .into());
+        }
+
+        let mut deployed = Vec::new();
+        for (source, target) in targets {
+            let path = install_path.join(&target);
+            crate::utils::create_parent_dirs(&RealFs, &path)?;
+            // Recorded before deploying so a partial copy is removed again
+            deployed.push(target);
+            let result = match self.mode {
+                LinkMode::Copy if source.is_dir() => copy_dir(&source, &path),
+                _ => self.link(&source, &path),
+            };
+            if let Err(error) = result {
+                self.deployed = deployed;
+                return Err(error);
+            }
+        }
+
+        log::info!(
+            "Deployed linked mod '{}' from '{}': {}",
+            id,
+            self.source,
+            deployed.join(", ")
+        );
+        self.deployed = deployed;
+        Ok(())
+    }
+
+    pub fn undeploy(&mut self, install_path: &Path) -> anyhow::Result<()> {
+        while let Some(file) = self.deployed.pop() {
+            let path = install_path.join(&file);
+            if let Err(error) = remove_link_or_file(&path) {
+                self.deployed.push(file.clone());
+                return Err(error).with_context(|| format!("Failed to remove '{}'", file));
+            }
+        }
+        Ok(())
+    }
+
+    fn link(&self, from: &Path, to: &Path) -> anyhow::Result<()> {
+        let result = match self.mode {
+            LinkMode::Symlink => symlink(from, to),
+            LinkMode::Copy => std::fs::copy(from, to).map(|_| ()),
+        };
+        result.with_context(|| {
+            format!(
+                "Failed to {} '{}' to '{}'",
+                if self.mode == LinkMode::Symlink {
+                    "link"
+                } else {
+                    "copy"
+                },
+                from.to_string_lossy(),
+                to.to_string_lossy()
+            )
+        })
+    }
+}
+
+pub fn snapshot(dir: &Path) -> Snapshot {
+    let mut snapshot = HashMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match dir.read_dir() {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => dirs.push(path),
+                Ok(metadata) => {
+                    snapshot.insert(path, (metadata.modified().ok(), metadata.len()));
+                }
+                Err(_) => (),
+            }
+        }
+    }
+    snapshot
+}
+
+fn copy_dir(from: &Path, to: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(to)
+        .with_context(|| format!("Failed to create directory '{}'", to.to_string_lossy()))?;
+    let entries = from
+        .read_dir()
+        .with_context(|| format!("Failed to list files in '{}'", from.to_string_lossy()))?;
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("Failed to list files in '{}'", from.to_string_lossy()))?
+            .path();
+        let target = to.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target).with_context(|| {
+                format!(
+                    "Failed to copy '{}' to '{}'",
+                    path.to_string_lossy(),
+                    target.to_string_lossy()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(from, to)
+}
+
+#[cfg(windows)]
+fn symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(from, to)
+    } else {
+        std::os::windows::fs::symlink_file(from, to)
+    }
+}
+
+/// Removes a symlink without touching its target, or a regular file or directory.
+fn remove_link_or_file(path: &Path) -> std::io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            std::fs::remove_file(path).or_else(|_| std::fs::remove_dir(path))
+        }
+        Ok(_) => crate::utils::remove_file_or_dir(&RealFs, path),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error),
+    }
+}
@@ -0,0 +1,52 @@
+//! Messages and questions for the user.
+//!
+//! Front-ends install a [`Dialogs`] implementation with [`set_handler`]. Without one, messages
+//! are only logged and questions are answered with their default answer.
+
+use std::sync::OnceLock;
+
+use crate::data::MessageIcon;
+
+pub trait Dialogs: Send + Sync {
+    fn message(&self, title: &str, message: &str, icon: MessageIcon);
+    /// Asks a yes or no question. `default` is the preselected answer.
+    fn confirm(&self, title: &str, message: &str, icon: MessageIcon, default: bool) -> bool;
+}
+
+static HANDLER: OnceLock<Box<dyn Dialogs>> = OnceLock::new();
+
+/// Sets the handler for all dialogs. Can only be called once.
+pub fn set_handler(handler: impl Dialogs + 'static) {
+    if HANDLER.set(Box::new(handler)).is_err() {
+        log::warn!("Dialog handler is already set");
+    }
+}
+
+pub fn message(title: &str, message: &str, icon: MessageIcon) {
+    match HANDLER.get() {
+        Some(handler) => handler.message(title, message, icon),
+        None => log::info!("{}: {}", title, message),
+    }
+}
+
+pub fn confirm(title: &str, message: &str, icon: MessageIcon, default: bool) -> bool {
+    match HANDLER.get() {
+        Some(handler) => handler.confirm(title, message, icon, default),
+        None => {
+            log::warn!(
+                "{}: {} Answering {}.",
+                title,
+                message,
+                if default { "yes" } else { "no" }
+            );
+            default
+        }
+    }
+}
+
+pub fn show_error(msg: &str) {
+    log::error!("{}", msg);
+    if let Some(handler) = HANDLER.get() {
+        handler.message("Error", msg, MessageIcon::Error);
+    }
+}
@@ -0,0 +1,695 @@
+//! Minimal reader for .NET assembly metadata (ECMA-335).
+//!
+//! Only supports what's needed to read the BepInEx plugin attributes (`BepInPlugin`,
+//! `BepInDependency` and `BepInProcess`) and the assembly name and version without loading
+//! or running anything.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{bail, ensure, Context};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::fs::Fs;
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct AssemblyInfo {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub plugins: Vec<PluginInfo>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct PluginInfo {
+    /// Full name of the class the attributes are declared on
+    pub type_name: String,
+    pub guid: String,
+    pub name: String,
+    pub version: String,
+    pub dependencies: Vec<PluginDependency>,
+    pub processes: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct PluginDependency {
+    pub guid: String,
+    pub soft: bool,
+    pub min_version: Option<String>,
+}
+
+pub fn read_assembly(fs: &dyn Fs, path: &Path) -> anyhow::Result<AssemblyInfo> {
+    let bytes = fs
+        .read(path)
+        .with_context(|| format!("Failed to read '{}'", path.to_string_lossy()))?;
+    parse_assembly(&bytes)
+        .with_context(|| format!("Failed to read metadata of '{}'", path.to_string_lossy()))
+}
+
+pub fn parse_assembly(bytes: &[u8]) -> anyhow::Result<AssemblyInfo> {
+    let metadata = Metadata::parse(bytes)?;
+    Ok(AssemblyInfo {
+        name: metadata.assembly_name()?,
+        version: metadata.assembly_version()?,
+        plugins: metadata.plugins()?,
+    })
+}
+
+mod table {
+    pub const MODULE: usize = 0x00;
+    pub const TYPE_REF: usize = 0x01;
+    pub const TYPE_DEF: usize = 0x02;
+    pub const FIELD_PTR: usize = 0x03;
+    pub const FIELD: usize = 0x04;
+    pub const METHOD_PTR: usize = 0x05;
+    pub const METHOD_DEF: usize = 0x06;
+    pub const PARAM_PTR: usize = 0x07;
+    pub const PARAM: usize = 0x08;
+    pub const INTERFACE_IMPL: usize = 0x09;
+    pub const MEMBER_REF: usize = 0x0A;
+    pub const CONSTANT: usize = 0x0B;
+    pub const CUSTOM_ATTRIBUTE: usize = 0x0C;
+    pub const FIELD_MARSHAL: usize = 0x0D;
+    pub const DECL_SECURITY: usize = 0x0E;
+    pub const CLASS_LAYOUT: usize = 0x0F;
+    pub const FIELD_LAYOUT: usize = 0x10;
+    pub const STAND_ALONE_SIG: usize = 0x11;
+    pub const EVENT_MAP: usize = 0x12;
+    pub const EVENT_PTR: usize = 0x13;
+    pub const EVENT: usize = 0x14;
+    pub const PROPERTY_MAP: usize = 0x15;
+    pub const PROPERTY_PTR: usize = 0x16;
+    pub const PROPERTY: usize = 0x17;
+    pub const METHOD_SEMANTICS: usize = 0x18;
+    pub const METHOD_IMPL: usize = 0x19;
+    pub const MODULE_REF: usize = 0x1A;
+    pub const TYPE_SPEC: usize = 0x1B;
+    pub const IMPL_MAP: usize = 0x1C;
+    pub const FIELD_RVA: usize = 0x1D;
+    pub const ENC_LOG: usize = 0x1E;
+    pub const ENC_MAP: usize = 0x1F;
+    pub const ASSEMBLY: usize = 0x20;
+    pub const ASSEMBLY_REF: usize = 0x23;
+    pub const FILE: usize = 0x26;
+    pub const EXPORTED_TYPE: usize = 0x27;
+    pub const MANIFEST_RESOURCE: usize = 0x28;
+    pub const GENERIC_PARAM: usize = 0x2A;
+    pub const METHOD_SPEC: usize = 0x2B;
+    pub const GENERIC_PARAM_CONSTRAINT: usize = 0x2C;
+    /// Placeholder for unused coded index tags
+    pub const NONE: usize = 0x3F;
+}
+
+#[derive(Clone, Copy)]
+enum Coded {
+    TypeDefOrRef,
+    HasConstant,
+    HasCustomAttribute,
+    HasFieldMarshal,
+    HasDeclSecurity,
+    MemberRefParent,
+    HasSemantics,
+    MethodDefOrRef,
+    MemberForwarded,
+    CustomAttributeType,
+    ResolutionScope,
+}
+
+impl Coded {
+    fn tables(self) -> &'static [usize] {
+        use table::*;
+        match self {
+            Coded::TypeDefOrRef => &[TYPE_DEF, TYPE_REF, TYPE_SPEC],
+            Coded::HasConstant => &[FIELD, PARAM, PROPERTY],
+            Coded::HasCustomAttribute => &[
+                METHOD_DEF,
+                FIELD,
+                TYPE_REF,
+                TYPE_DEF,
+                PARAM,
+                INTERFACE_IMPL,
+                MEMBER_REF,
+                MODULE,
+                DECL_SECURITY,
+                PROPERTY,
+                EVENT,
+                STAND_ALONE_SIG,
+                MODULE_REF,
+                TYPE_SPEC,
+                ASSEMBLY,
+                ASSEMBLY_REF,
+                FILE,
+                EXPORTED_TYPE,
+                MANIFEST_RESOURCE,
+                GENERIC_PARAM,
+                GENERIC_PARAM_CONSTRAINT,
+                METHOD_SPEC,
+            ],
+            Coded::HasFieldMarshal => &[FIELD, PARAM],
+            Coded::HasDeclSecurity => &[TYPE_DEF, METHOD_DEF, ASSEMBLY],
+            Coded::MemberRefParent => &[TYPE_DEF, TYPE_REF, MODULE_REF, METHOD_DEF, TYPE_SPEC],
+            Coded::HasSemantics => &[EVENT, PROPERTY],
+            Coded::MethodDefOrRef => &[METHOD_DEF, MEMBER_REF],
+            Coded::MemberForwarded => &[FIELD, METHOD_DEF],
+            Coded::CustomAttributeType => &[NONE, NONE, METHOD_DEF, MEMBER_REF, NONE],
+            Coded::ResolutionScope => &[MODULE, MODULE_REF, ASSEMBLY_REF, TYPE_REF],
+        }
+    }
+
+    fn tag_bits(self) -> u32 {
+        let n = self.tables().len() as u32;
+        32 - (n - 1).leading_zeros()
+    }
+
+    /// Splits a coded index into table and (1-based) row
+    fn decode(self, value: u32) -> (usize, u32) {
+        let bits = self.tag_bits();
+        let tag = (value & ((1 << bits) - 1)) as usize;
+        let table = self.tables().get(tag).copied().unwrap_or(table::NONE);
+        (table, value >> bits)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Col {
+    U16,
+    U32,
+    Str,
+    Guid,
+    Blob,
+    Index(usize),
+    CodedIndex(Coded),
+}
+
+fn schema(table: usize) -> Option<&'static [Col]> {
+    use table::*;
+    use Coded::*;
+    use Col::*;
+    Some(match table {
+        MODULE => &[U16, Str, Guid, Guid, Guid],
+        TYPE_REF => &[CodedIndex(ResolutionScope), Str, Str],
+        TYPE_DEF => &[
+            U32,
+            Str,
+            Str,
+            CodedIndex(TypeDefOrRef),
+            Index(FIELD),
+            Index(METHOD_DEF),
+        ],
+        FIELD_PTR => &[Index(FIELD)],
+        FIELD => &[U16, Str, Blob],
+        METHOD_PTR => &[Index(METHOD_DEF)],
+        METHOD_DEF => &[U32, U16, U16, Str, Blob, Index(PARAM)],
+        PARAM_PTR => &[Index(PARAM)],
+        PARAM => &[U16, U16, Str],
+        INTERFACE_IMPL => &[Index(TYPE_DEF), CodedIndex(TypeDefOrRef)],
+        MEMBER_REF => &[CodedIndex(MemberRefParent), Str, Blob],
+        CONSTANT => &[U16, CodedIndex(HasConstant), Blob],
+        CUSTOM_ATTRIBUTE => &[
+            CodedIndex(HasCustomAttribute),
+            CodedIndex(CustomAttributeType),
+            Blob,
+        ],
+        FIELD_MARSHAL => &[CodedIndex(HasFieldMarshal), Blob],
+        DECL_SECURITY => &[U16, CodedIndex(HasDeclSecurity), Blob],
+        CLASS_LAYOUT => &[U16, U32, Index(TYPE_DEF)],
+        FIELD_LAYOUT => &[U32, Index(FIELD)],
+        STAND_ALONE_SIG => &[Blob],
+        EVENT_MAP => &[Index(TYPE_DEF), Index(EVENT)],
+        EVENT_PTR => &[Index(EVENT)],
+        EVENT => &[U16, Str, CodedIndex(TypeDefOrRef)],
+        PROPERTY_MAP => &[Index(TYPE_DEF), Index(PROPERTY)],
+        PROPERTY_PTR => &[Index(PROPERTY)],
+        PROPERTY => &[U16, Str, Blob],
+        METHOD_SEMANTICS => &[U16, Index(METHOD_DEF), CodedIndex(HasSemantics)],
+        METHOD_IMPL => &[
+            Index(TYPE_DEF),
+            CodedIndex(MethodDefOrRef),
+            CodedIndex(MethodDefOrRef),
+        ],
+        MODULE_REF => &[Str],
+        TYPE_SPEC => &[Blob],
+        IMPL_MAP => &[U16, CodedIndex(MemberForwarded), Str, Index(MODULE_REF)],
+        FIELD_RVA => &[U32, Index(FIELD)],
+        ENC_LOG => &[U32, U32],
+        ENC_MAP => &[U32],
+        ASSEMBLY => &[U32, U16, U16, U16, U16, U32, Blob, Str, Str],
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Copy, Default)]
+struct Table {
+    rows: u32,
+    row_size: usize,
+    offset: usize,
+}
+
+struct Metadata<'a> {
+    tables: &'a [u8],
+    strings: &'a [u8],
+    blobs: &'a [u8],
+    table_info: [Table; 64],
+    row_counts: [u32; 64],
+    wide_strings: bool,
+    wide_guids: bool,
+    wide_blobs: bool,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], pos: usize) -> Self {
+        Self { bytes, pos }
+    }
+
+    fn take(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self.pos.checked_add(n).context("Invalid offset")?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .context("Unexpected end of data")?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> anyhow::Result<u64> {
+        Ok(self.u32()? as u64 | (self.u32()? as u64) << 32)
+    }
+
+    /// Compressed unsigned integer as used in blobs and signatures (II.23.2)
+    fn compressed(&mut self) -> anyhow::Result<u32> {
+        let first = self.u8()? as u32;
+        if first & 0x80 == 0 {
+            Ok(first)
+        } else if first & 0xC0 == 0x80 {
+            Ok((first & 0x3F) << 8 | self.u8()? as u32)
+        } else if first & 0xE0 == 0xC0 {
+            let rest = self.take(3)?;
+            Ok((first & 0x1F) << 24
+                | (rest[0] as u32) << 16
+                | (rest[1] as u32) << 8
+                | rest[2] as u32)
+        } else {
+            bail!("Invalid compressed integer")
+        }
+    }
+
+    /// SerString from a custom attribute blob. `None` for a null string.
+    fn ser_string(&mut self) -> anyhow::Result<Option<String>> {
+        if self.bytes.get(self.pos) == Some(&0xFF) {
+            self.pos += 1;
+            return Ok(None);
+        }
+        let len = self.compressed()? as usize;
+        Ok(Some(String::from_utf8_lossy(self.take(len)?).into_owned()))
+    }
+}
+
+/// Translates a relative virtual address into a file offset using the PE section table
+fn rva_to_offset(sections: &[(u32, u32, u32)], rva: u32) -> anyhow::Result<usize> {
+    let (offset, raw) = sections
+        .iter()
+        .find_map(|&(address, size, raw)| {
+            let offset = rva.checked_sub(address)?;
+            (offset < size).then_some((offset, raw))
+        })
+        .with_context(|| format!("RVA {:#x} is not part of any section", rva))?;
+    let offset = offset
+        .checked_add(raw)
+        .with_context(|| format!("RVA {:#x} is outside of the file", rva))?;
+    Ok(offset as usize)
+}
+
+impl<'a> Metadata<'a> {
+    fn parse(bytes: &'a [u8]) -> anyhow::Result<Self> {
+        ensure!(bytes.get(..2) == Some(b"MZ"), "Not a PE file");
+        let pe = Reader::new(bytes, 0x3C).u32()? as usize;
+        // Offsets below are relative to `pe` and can't overflow once it's inside the file
+        ensure!(
+            pe < bytes.len() && bytes.get(pe..pe + 4) == Some(b"PE\0\0"),
+            "Not a PE file"
+        );
+
+        let mut coff = Reader::new(bytes, pe + 4);
+        coff.take(2)?;
+        let section_count = coff.u16()? as usize;
+        coff.take(12)?;
+        let optional_size = coff.u16()? as usize;
+        let optional = pe + 24;
+
+        let magic = Reader::new(bytes, optional).u16()?;
+        let directories = match magic {
+            0x10B => optional + 96,
+            0x20B => optional + 112,
+            _ => bail!("Unknown PE optional header magic: {:#x}", magic),
+        };
+        let mut cli_dir = Reader::new(bytes, directories + 14 * 8);
+        let cli_rva = cli_dir.u32()?;
+        ensure!(cli_rva != 0, "Not a .NET assembly");
+
+        let mut sections = Vec::with_capacity(section_count);
+        let mut section = Reader::new(bytes, optional + optional_size);
+        for _ in 0..section_count {
+            section.take(8)?;
+            let virtual_size = section.u32()?;
+            let virtual_address = section.u32()?;
+            let raw_size = section.u32()?;
+            let raw_offset = section.u32()?;
+            section.take(16)?;
+            sections.push((virtual_address, virtual_size.max(raw_size), raw_offset));
+        }
+
+        let mut cli = Reader::new(bytes, rva_to_offset(&sections, cli_rva)?);
+        cli.take(8)?;
+        let metadata_offset = rva_to_offset(&sections, cli.u32()?)?;
+
+        let mut root = Reader::new(bytes, metadata_offset);
+        ensure!(root.u32()? == 0x424A_5342, "Invalid metadata signature");
+        root.take(8)?;
+        let version_len = root.u32()? as usize;
+        root.take(version_len)?;
+        root.take(2)?;
+        let stream_count = root.u16()?;
+
+        let mut streams = HashMap::new();
+        for _ in 0..stream_count {
+            let offset = root.u32()? as usize;
+            let size = root.u32()? as usize;
+            let start = root.pos;
+            let name_len = bytes[start..]
+                .iter()
+                .position(|&b| b == 0)
+                .context("Invalid stream name")?;
+            let name = String::from_utf8_lossy(&bytes[start..start + name_len]).into_owned();
+            root.pos = start + (name_len + 4) / 4 * 4;
+            let data = metadata_offset
+                .checked_add(offset)
+                .and_then(|start| Some(start..start.checked_add(size)?))
+                .and_then(|range| bytes.get(range))
+                .context("Stream out of bounds")?;
+            streams.insert(name, data);
+        }
+
+        let tables = streams
+            .get("#~")
+            .or_else(|| streams.get("#-"))
+            .copied()
+            .context("Missing metadata tables stream")?;
+        let strings = streams.get("#Strings").copied().unwrap_or_default();
+        let blobs = streams.get("#Blob").copied().unwrap_or_default();
+
+        let mut header = Reader::new(tables, 6);
+        let heap_sizes = header.u8()?;
+        header.u8()?;
+        let valid = header.u64()?;
+        header.u64()?;
+        let mut row_counts = [0u32; 64];
+        for (i, count) in row_counts.iter_mut().enumerate() {
+            if valid & (1 << i) != 0 {
+                *count = header.u32()?;
+            }
+        }
+
+        let mut metadata = Metadata {
+            tables,
+            strings,
+            blobs,
+            table_info: [Table::default(); 64],
+            row_counts,
+            wide_strings: heap_sizes & 0x01 != 0,
+            wide_guids: heap_sizes & 0x02 != 0,
+            wide_blobs: heap_sizes & 0x04 != 0,
+        };
+
+        let mut offset = header.pos;
+        for (i, &rows) in row_counts.iter().enumerate() {
+            if rows == 0 {
+                continue;
+            }
+            let schema = match schema(i) {
+                Some(schema) => schema,
+                // Tables after the ones we know about are never read, so their size doesn't matter
+                None if i > table::ASSEMBLY => break,
+                None => bail!("Unsupported metadata table {:#x}", i),
+            };
+            let row_size = schema.iter().map(|col| metadata.col_size(*col)).sum();
+            metadata.table_info[i] = Table {
+                rows,
+                row_size,
+                offset,
+            };
+            offset += row_size * rows as usize;
+        }
+
+        Ok(metadata)
+    }
+
+    fn col_size(&self, col: Col) -> usize {
+        match col {
+            Col::U16 => 2,
+            Col::U32 => 4,
+            Col::Str => self.heap_size(self.wide_strings),
+            Col::Guid => self.heap_size(self.wide_guids),
+            Col::Blob => self.heap_size(self.wide_blobs),
+            Col::Index(table) => {
+                if self.row_counts[table] < 1 << 16 {
+                    2
+                } else {
+                    4
+                }
+            }
+            Col::CodedIndex(coded) => {
+                let max_rows = coded
+                    .tables()
+                    .iter()
+                    .filter(|&&t| t != table::NONE)
+                    .map(|&t| self.row_counts[t])
+                    .max()
+                    .unwrap_or(0);
+                if max_rows < 1 << (16 - coded.tag_bits()) {
+                    2
+                } else {
+                    4
+                }
+            }
+        }
+    }
+
+    fn heap_size(&self, wide: bool) -> usize {
+        if wide {
+            4
+        } else {
+            2
+        }
+    }
+
+    /// Reads all columns of a (1-based) row as integers
+    fn row(&self, table: usize, row: u32) -> anyhow::Result<Vec<u32>> {
+        let info = self.table_info[table];
+        ensure!(
+            row >= 1 && row <= info.rows,
+            "Row {} of table {:#x} out of range",
+            row,
+            table
+        );
+        let schema = schema(table).context("Unsupported metadata table")?;
+        let mut reader = Reader::new(
+            self.tables,
+            info.offset + info.row_size * (row - 1) as usize,
+        );
+        schema
+            .iter()
+            .map(|col| match self.col_size(*col) {
+                2 => reader.u16().map(u32::from),
+                _ => reader.u32(),
+            })
+            .collect()
+    }
+
+    fn string(&self, index: u32) -> anyhow::Result<String> {
+        let data = self
+            .strings
+            .get(index as usize..)
+            .context("String index out of range")?;
+        let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        Ok(String::from_utf8_lossy(&data[..len]).into_owned())
+    }
+
+    fn blob(&self, index: u32) -> anyhow::Result<&'a [u8]> {
+        let mut reader = Reader::new(self.blobs, index as usize);
+        let len = reader.compressed()? as usize;
+        reader.take(len)
+    }
+
+    fn assembly_name(&self) -> anyhow::Result<Option<String>> {
+        if self.table_info[table::ASSEMBLY].rows == 0 {
+            return Ok(None);
+        }
+        let row = self.row(table::ASSEMBLY, 1)?;
+        self.string(row[7]).map(Some)
+    }
+
+    fn assembly_version(&self) -> anyhow::Result<Option<String>> {
+        if self.table_info[table::ASSEMBLY].rows == 0 {
+            return Ok(None);
+        }
+        let row = self.row(table::ASSEMBLY, 1)?;
+        Ok(Some(format!("{}.{}.{}.{}", row[1], row[2], row[3], row[4])))
+    }
+
+    fn type_def_name(&self, row: u32) -> anyhow::Result<String> {
+        let row = self.row(table::TYPE_DEF, row)?;
+        let name = self.string(row[1])?;
+        let namespace = self.string(row[2])?;
+        Ok(if namespace.is_empty() {
+            name
+        } else {
+            format!("{}.{}", namespace, name)
+        })
+    }
+
+    /// Returns the full name of the attribute type and the constructor signature
+    fn attribute_ctor(&self, attribute_type: u32) -> anyhow::Result<Option<(String, &'a [u8])>> {
+        let (table, row) = Coded::CustomAttributeType.decode(attribute_type);
+        if table != table::MEMBER_REF {
+            // Attributes defined in the same assembly. Not relevant for plugins.
+            return Ok(None);
+        }
+        let member = self.row(table::MEMBER_REF, row)?;
+        let (parent_table, parent_row) = Coded::MemberRefParent.decode(member[0]);
+        if parent_table != table::TYPE_REF {
+            return Ok(None);
+        }
+        let type_ref = self.row(table::TYPE_REF, parent_row)?;
+        let name = format!(
+            "{}.{}",
+            self.string(type_ref[2])?,
+            self.string(type_ref[1])?
+        );
+        Ok(Some((name, self.blob(member[2])?)))
+    }
+
+    fn plugins(&self) -> anyhow::Result<Vec<PluginInfo>> {
+        let mut plugins: Vec<(u32, PluginInfo)> = Vec::new();
+        let mut dependencies: Vec<(u32, PluginDependency)> = Vec::new();
+        let mut processes: Vec<(u32, String)> = Vec::new();
+
+        for i in 1..=self.table_info[table::CUSTOM_ATTRIBUTE].rows {
+            let row = self.row(table::CUSTOM_ATTRIBUTE, i)?;
+            let (parent_table, parent) = Coded::HasCustomAttribute.decode(row[0]);
+            if parent_table != table::TYPE_DEF {
+                continue;
+            }
+            let (name, signature) = match self.attribute_ctor(row[1])? {
+                Some(ctor) => ctor,
+                None => continue,
+            };
+            let params = ctor_params(signature)?;
+            let mut value = Reader::new(self.blob(row[2])?, 0);
+            ensure!(value.u16()? == 0x0001, "Invalid custom attribute prolog");
+
+            match name.as_str() {
+                "BepInEx.BepInPlugin" => {
+                    ensure!(params.len() == 3, "Unexpected BepInPlugin constructor");
+                    plugins.push((
+                        parent,
+                        PluginInfo {
+                            type_name: self.type_def_name(parent)?,
+                            guid: value.ser_string()?.unwrap_or_default(),
+                            name: value.ser_string()?.unwrap_or_default(),
+                            version: value.ser_string()?.unwrap_or_default(),
+                            dependencies: Vec::new(),
+                            processes: Vec::new(),
+                        },
+                    ));
+                }
+                "BepInEx.BepInDependency" => {
+                    let guid = value.ser_string()?.unwrap_or_default();
+                    let mut dependency = PluginDependency {
+                        guid,
+                        soft: false,
+                        min_version: None,
+                    };
+                    match params.get(1) {
+                        Some(Param::String) => dependency.min_version = value.ser_string()?,
+                        // DependencyFlags: HardDependency = 1, SoftDependency = 2
+                        Some(Param::Int32) => dependency.soft = value.u32()? & 2 != 0,
+                        _ => (),
+                    }
+                    dependencies.push((parent, dependency));
+                }
+                "BepInEx.BepInProcess" => {
+                    if let Some(process) = value.ser_string()? {
+                        processes.push((parent, process));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        for (parent, dependency) in dependencies {
+            if let Some((_, plugin)) = plugins.iter_mut().find(|(p, _)| *p == parent) {
+                plugin.dependencies.push(dependency);
+            }
+        }
+        for (parent, process) in processes {
+            if let Some((_, plugin)) = plugins.iter_mut().find(|(p, _)| *p == parent) {
+                plugin.processes.push(process);
+            }
+        }
+
+        Ok(plugins.into_iter().map(|(_, plugin)| plugin).collect())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Param {
+    String,
+    /// Int32 or an enum (which is assumed to be backed by an Int32)
+    Int32,
+    Other,
+}
+
+/// Parameter types of a constructor from its MethodRefSig (II.23.2.2)
+fn ctor_params(signature: &[u8]) -> anyhow::Result<Vec<Param>> {
+    let mut reader = Reader::new(signature, 0);
+    reader.u8()?;
+    let count = reader.compressed()?;
+    ensure!(reader.u8()? == 0x01, "Constructor doesn't return void");
+    let mut params = Vec::new();
+    for _ in 0..count {
+        let param = match reader.u8()? {
+            0x0E => Param::String,
+            0x08 => Param::Int32,
+            0x11 => {
+                reader.compressed()?;
+                Param::Int32
+            }
+            _ => Param::Other,
+        };
+        let is_other = param == Param::Other;
+        params.push(param);
+        if is_other {
+            // Can't know the size of unknown types, so stop here
+            break;
+        }
+    }
+    Ok(params)
+}
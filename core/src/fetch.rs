@@ -0,0 +1,41 @@
+//! Downloads over HTTP. Tests replace [`HttpFetcher`] to serve fixtures instead of the internet.
+
+use anyhow::Context;
+
+/// Debug builds download `https://<host>/<path>` from `<mirror>/<host>/<path>` if this environment
+/// variable is set to a mirror URL, e.g. for end-to-end tests against a local metadata server.
+#[cfg(debug_assertions)]
+static MIRROR_ENV: &str = "YTINU_MIRROR";
+
+pub trait Fetcher: Send + Sync {
+    /// Downloads a file into memory, failing on HTTP error status codes.
+    fn fetch(&self, url: &str) -> anyhow::Result<Vec<u8>>;
+}
+
+pub struct HttpFetcher;
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        let url = &mirrored(url);
+        let response = reqwest::blocking::get(url)
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to download '{}'", url))?;
+        let bytes = response
+            .bytes()
+            .with_context(|| format!("Failed to download '{}'", url))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(debug_assertions)]
+fn mirrored(url: &str) -> String {
+    match (std::env::var(MIRROR_ENV), url.strip_prefix("https://")) {
+        (Ok(mirror), Some(path)) => format!("{}/{}", mirror.trim_end_matches('/'), path),
+        _ => url.to_string(),
+    }
+}
+
+#[cfg(not(debug_assertions))]
+fn mirrored(url: &str) -> String {
+    url.to_string()
+}
@@ -0,0 +1,302 @@
+//! Filesystem used to install mods and the mod loader into game directories.
+//!
+//! [`RealFs`] uses `std::fs`. [`MemoryFs`] keeps everything in memory so the install flows can be
+//! tested without touching any real game directory.
+//!
+//! Everything in a game directory goes through the [`Env`](crate::Env) of the manager: installing
+//! and removing, reading plugin assemblies and configs and detecting manually installed mods. Some
+//! paths always use the real filesystem:
+//!
+//! - ytinu's own data, config and cache files (`data.json`, `config.json`, the lock file and logs)
+//! - files the user picks outside of the game directory: local mod files ([`crate::local_mod`])
+//!   and mods packed with [`crate::pack`]
+//! - linked build directories and their deployment into the game directory ([`crate::dev_link`]),
+//!   because links are deployed as symlinks
+//! - the game's launch scripts ([`crate::launch`])
+//! - the process list of the operating system ([`crate::process`])
+
+use std::{
+    collections::BTreeMap,
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub trait Fs: Send + Sync {
+    fn exists(&self, path: &Path) -> bool;
+    fn is_file(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    /// Reads at most the last `max_len` bytes of a file. Also returns whether the start was cut
+    /// off.
+    fn read_end(&self, path: &Path, max_len: u64) -> io::Result<(Vec<u8>, bool)> {
+        let mut data = self.read(path)?;
+        let start = data.len().saturating_sub(max_len as usize);
+        data.drain(..start);
+        Ok((data, start > 0))
+    }
+    /// Creates or truncates a file. The parent directory has to exist.
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    /// Paths of all entries of a directory
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+    /// Sets the permissions of an extracted file. Ignored on Windows.
+    fn set_unix_mode(&self, path: &Path, mode: u32) -> io::Result<()>;
+}
+
+pub struct RealFs;
+
+impl Fs for RealFs {
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn read_end(&self, path: &Path, max_len: u64) -> io::Result<(Vec<u8>, bool)> {
+        let mut file = std::fs::File::open(path)?;
+        let start = file.metadata()?.len().saturating_sub(max_len);
+        file.seek(SeekFrom::Start(start))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Ok((data, start > 0))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        std::fs::write(path, data)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        path.read_dir()?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::copy(from, to).map(|_| ())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_dir_all(path)
+    }
+
+    #[cfg(unix)]
+    fn set_unix_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+    }
+
+    #[cfg(not(unix))]
+    fn set_unix_mode(&self, _path: &Path, _mode: u32) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Entry {
+    File(Vec<u8>),
+    Dir,
+}
+
+/// Filesystem that only exists in memory. Paths are used as given, without resolving `..` or
+/// symlinks. Root directories always exist.
+#[derive(Default)]
+pub struct MemoryFs {
+    entries: Mutex<BTreeMap<PathBuf, Entry>>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a file and its parent directories
+    pub fn add_file(&self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent).unwrap();
+        }
+        self.write(path, &data.into()).unwrap();
+    }
+
+    /// All files and directories below `dir`, relative to it and sorted. Directories end with `/`.
+    pub fn tree(&self, dir: impl AsRef<Path>) -> Vec<String> {
+        let dir = dir.as_ref();
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .filter_map(|(path, entry)| {
+                let relative = path.strip_prefix(dir).ok()?.to_str()?.replace('\\', "/");
+                match entry {
+                    _ if relative.is_empty() => None,
+                    Entry::File(_) => Some(relative),
+                    Entry::Dir => Some(relative + "/"),
+                }
+            })
+            .collect()
+    }
+
+    fn check_parent(entries: &BTreeMap<PathBuf, Entry>, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if !is_root(parent) => match entries.get(parent) {
+                Some(Entry::Dir) => Ok(()),
+                Some(Entry::File(_)) => Err(error(ErrorKind::Other, "Not a directory", parent)),
+                None => Err(not_found(parent)),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_root(path: &Path) -> bool {
+    path.parent().is_none() || path.as_os_str().is_empty()
+}
+
+fn error(kind: ErrorKind, message: &str, path: &Path) -> io::Error {
+    io::Error::new(kind, format!("{}: '{}'", message, path.to_string_lossy()))
+}
+
+fn not_found(path: &Path) -> io::Error {
+    error(ErrorKind::NotFound, "No such file or directory", path)
+}
+
+impl Fs for MemoryFs {
+    fn exists(&self, path: &Path) -> bool {
+        is_root(path) || self.entries.lock().unwrap().contains_key(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        matches!(self.entries.lock().unwrap().get(path), Some(Entry::File(_)))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        is_root(path) || matches!(self.entries.lock().unwrap().get(path), Some(Entry::Dir))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.entries.lock().unwrap().get(path) {
+            Some(Entry::File(data)) => Ok(data.clone()),
+            Some(Entry::Dir) => Err(error(ErrorKind::Other, "Is a directory", path)),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        Self::check_parent(&entries, path)?;
+        if let Some(Entry::Dir) = entries.get(path) {
+            return Err(error(ErrorKind::Other, "Is a directory", path));
+        }
+        entries.insert(path.to_path_buf(), Entry::File(data.to_vec()));
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        for dir in path.ancestors().filter(|dir| !is_root(dir)) {
+            match entries.get(dir) {
+                Some(Entry::File(_)) => {
+                    return Err(error(ErrorKind::AlreadyExists, "File exists", dir))
+                }
+                Some(Entry::Dir) => break,
+                None => {
+                    entries.insert(dir.to_path_buf(), Entry::Dir);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.is_dir(path) {
+            return Err(not_found(path));
+        }
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .keys()
+            .filter(|entry| entry.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(from) {
+            return Err(not_found(from));
+        }
+        Self::check_parent(&entries, to)?;
+        let moved: Vec<PathBuf> = entries
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect();
+        for path in moved {
+            let entry = entries.remove(&path).unwrap();
+            let target = to.join(path.strip_prefix(from).unwrap());
+            entries.insert(target, entry);
+        }
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let data = self.read(from)?;
+        self.write(to, &data)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(path) {
+            Some(Entry::File(_)) => {
+                entries.remove(path);
+                Ok(())
+            }
+            Some(Entry::Dir) => Err(error(ErrorKind::Other, "Is a directory", path)),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(path) {
+            Some(Entry::Dir) => {
+                entries.retain(|entry, _| !entry.starts_with(path));
+                Ok(())
+            }
+            Some(Entry::File(_)) => Err(error(ErrorKind::Other, "Not a directory", path)),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn set_unix_mode(&self, path: &Path, _mode: u32) -> io::Result<()> {
+        if self.exists(path) {
+            Ok(())
+        } else {
+            Err(not_found(path))
+        }
+    }
+}
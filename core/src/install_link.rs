@@ -0,0 +1,67 @@
+//! `ytinu://install/<game>/<mod>[@version]` links that let mod pages offer one-click installs.
+//!
+//! Links come from arbitrary websites, so they are only parsed into ids here. Installing always
+//! goes through the metadata and has to be confirmed by the user (see
+//! [`Manager::check_install_link`](crate::Manager::check_install_link)).
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{bail, ensure, Context};
+
+use crate::local_mod::validate_mod_id;
+
+pub static SCHEME: &str = "ytinu";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallLink {
+    pub game: String,
+    pub mod_id: String,
+    /// Version the website links to. The metadata has to offer exactly this version.
+    pub version: Option<semver::Version>,
+}
+
+impl FromStr for InstallLink {
+    type Err = anyhow::Error;
+
+    fn from_str(url: &str) -> anyhow::Result<Self> {
+        let rest = url
+            .get(..SCHEME.len() + 3)
+            .filter(|prefix| prefix.eq_ignore_ascii_case(&format!("{}://", SCHEME)))
+            .map(|prefix| &url[prefix.len()..])
+            .with_context(|| format!("Not a {}:// link", SCHEME))?;
+        // Browsers may add a trailing slash
+        let segments: Vec<&str> = rest.trim_end_matches('/').split('/').collect();
+        let (game, mod_id) = match segments.as_slice() {
+            ["install", game, mod_id] => (*game, *mod_id),
+            [action, ..] if *action != "install" => bail!("Unknown action '{}'", action),
+            _ => bail!("Expected {}://install/<game>/<mod>[@version]", SCHEME),
+        };
+        let (mod_id, version) = match mod_id.split_once('@') {
+            Some((mod_id, version)) => {
+                let version = version
+                    .parse()
+                    .with_context(|| format!("Invalid version '{}'", version))?;
+                (mod_id, Some(version))
+            }
+            None => (mod_id, None),
+        };
+        // Game ids follow the same rules as mod ids
+        ensure!(validate_mod_id(game).is_ok(), "Invalid game id '{}'", game);
+        validate_mod_id(mod_id)?;
+        Ok(InstallLink {
+            game: game.to_string(),
+            mod_id: mod_id.to_string(),
+            version,
+        })
+    }
+}
+
+impl fmt::Display for InstallLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://install/{}/{}", SCHEME, self.game, self.mod_id)?;
+        if let Some(version) = &self.version {
+            write!(f, "@{}", version)?;
+        }
+        Ok(())
+    }
+}
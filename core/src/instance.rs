@@ -0,0 +1,147 @@
+//! Makes sure only one ytinu process uses the data directory at a time.
+//!
+//! The running process holds an exclusive lock on `ytinu.lock` in the data directory. The GUI also
+//! publishes the port and session token of its server in `instance.json`, so a later launch can
+//! ask it to show its UI (see [`ActivateRequest`]) instead of starting a second server.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use app_dirs::AppDataType;
+use fs2::FileExt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::data::OpenUIConfig;
+
+/// Header with the session token that every API request has to include
+pub static TOKEN_HEADER: &str = "X-Ytinu-Token";
+static LOCK_FILE: &str = "ytinu.lock";
+static INFO_FILE: &str = "instance.json";
+/// How long a launch waits for the running instance to start its server or to exit
+static HAND_OFF_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Server of the running instance
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InstanceInfo {
+    pub pid: u32,
+    pub port: u16,
+    pub token: String,
+}
+
+/// Sent by a later launch to the running instance
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ActivateRequest {
+    /// UI requested on the command line. The configured UI is used if `null`.
+    pub ui: Option<OpenUIConfig>,
+    /// `ytinu://` link of a mod to install after asking the user
+    #[serde(default)]
+    pub install: Option<String>,
+}
+
+/// Exclusive lock on the data directory. Released when dropped or when the process exits.
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Locks the data directory. Returns `None` if another process holds the lock.
+    pub fn acquire() -> anyhow::Result<Option<Self>> {
+        let path = data_dir()?.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open '{}'", path.display()))?;
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Some(InstanceLock { _file: file })),
+            Err(error) if error.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(error) => {
+                Err(error).with_context(|| format!("Failed to lock '{}'", path.display()))
+            }
+        }
+    }
+
+    /// Locks the data directory or passes `request` on to the running instance. Returns `None`
+    /// after handing off. Waits for an instance that is still starting or about to exit.
+    pub fn acquire_or_hand_off(request: &ActivateRequest) -> anyhow::Result<Option<Self>> {
+        let start = Instant::now();
+        loop {
+            if let Some(lock) = Self::acquire()? {
+                return Ok(Some(lock));
+            }
+            if let Some(instance) = running_instance() {
+                match activate(&instance, request) {
+                    Ok(()) => return Ok(None),
+                    Err(error) => log::warn!("Failed to reach running instance: {:#}", error),
+                }
+            }
+            if start.elapsed() > HAND_OFF_TIMEOUT {
+                bail!("ytinu is already running but doesn't respond. Please close it first.");
+            }
+            std::thread::sleep(Duration::from_millis(250));
+        }
+    }
+
+    /// Lets later launches find the server of this process
+    pub fn publish(&self, port: u16, token: &str) -> anyhow::Result<()> {
+        let info = InstanceInfo {
+            pid: std::process::id(),
+            port,
+            token: token.to_string(),
+        };
+        let path = data_dir()?.join(INFO_FILE);
+        let mut options = OpenOptions::new();
+        options.create(true).write(true).truncate(true);
+        // The token allows controlling ytinu, so other users must not be able to read it
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&path)
+            .and_then(|mut file| file.write_all(&serde_json::to_vec(&info)?))
+            .with_context(|| format!("Failed to write '{}'", path.display()))
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        unpublish();
+    }
+}
+
+/// Removes the published server, e.g. before restarting after an update so the new process waits
+/// for this one to exit instead of handing off to it.
+pub fn unpublish() {
+    if let Ok(dir) = data_dir() {
+        let _ = std::fs::remove_file(dir.join(INFO_FILE));
+    }
+}
+
+/// The published server of the running instance, if any
+pub fn running_instance() -> Option<InstanceInfo> {
+    let data = std::fs::read(data_dir().ok()?.join(INFO_FILE)).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Asks the running instance to show its UI and to install the linked mod
+pub fn activate(instance: &InstanceInfo, request: &ActivateRequest) -> anyhow::Result<()> {
+    reqwest::blocking::Client::new()
+        .post(&format!("http://127.0.0.1:{}/api/activate", instance.port))
+        .header(TOKEN_HEADER, &instance.token)
+        .json(request)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("No response from ytinu (process {})", instance.pid))?;
+    Ok(())
+}
+
+fn data_dir() -> anyhow::Result<PathBuf> {
+    crate::utils::app_dir(AppDataType::UserData).context("Failed to get data directory")
+}
@@ -0,0 +1,201 @@
+use std::{
+    path::PathBuf,
+    process::{Child, Command, ExitStatus},
+};
+
+use anyhow::{bail, Context};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::data::SetupGame;
+
+/// Maximum number of launches remembered per game.
+pub static MAX_LAUNCH_HISTORY: usize = 50;
+
+#[derive(Deserialize, JsonSchema, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct LaunchOptions {
+    /// Start the game without loading BepInEx for this run only.
+    pub vanilla: bool,
+    /// Start the game through Steam instead of running the executable directly.
+    pub steam: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct LaunchRecord {
+    /// Unix timestamp (in seconds) of the launch.
+    pub started: u64,
+    /// Runtime in seconds. Only known for direct launches that have finished.
+    pub duration: Option<u64>,
+    pub vanilla: bool,
+    pub steam: bool,
+    pub exit_code: Option<i32>,
+}
+
+pub struct RunningGame {
+    pub game_id: String,
+    child: Child,
+    record: LaunchRecord,
+}
+
+impl RunningGame {
+    /// Returns the finished launch record if the game process has exited.
+    pub fn try_finish(&mut self) -> Option<LaunchRecord> {
+        match self.child.try_wait() {
+            Ok(Some(status)) => Some(self.finish(status)),
+            Ok(None) => None,
+            Err(error) => {
+                log::error!("Failed to check status of game process: {}", error);
+                Some(self.record.clone())
+            }
+        }
+    }
+
+    /// Blocks until the game process has exited.
+    pub fn wait(mut self) -> LaunchRecord {
+        match self.child.wait() {
+            Ok(status) => self.finish(status),
+            Err(error) => {
+                log::error!("Failed to wait for game process: {}", error);
+                self.record
+            }
+        }
+    }
+
+    /// Record for a game that is still running when ytinu stops tracking it.
+    pub fn abandon(self) -> LaunchRecord {
+        self.record
+    }
+
+    fn finish(&mut self, status: ExitStatus) -> LaunchRecord {
+        log::info!("'{}' exited with {}", self.game_id, status);
+        let mut record = self.record.clone();
+        record.exit_code = status.code();
+        record.duration = Some(crate::utils::unix_time().saturating_sub(record.started));
+        record
+    }
+}
+
+pub enum Launch {
+    /// Launched via Steam. Steam owns the process so the exit status is unknown.
+    Steam(LaunchRecord),
+    Process(RunningGame),
+}
+
+pub fn launch(game: &SetupGame, options: LaunchOptions) -> anyhow::Result<Launch> {
+    // Without an enabled BepInEx the game runs without mods whether requested or not
+    let vanilla = options.vanilla || !game.bep_in_ex.as_ref().is_some_and(|b| b.enabled);
+    let record = LaunchRecord {
+        started: crate::utils::unix_time(),
+        duration: None,
+        vanilla,
+        steam: options.steam,
+        exit_code: None,
+    };
+
+    if options.steam {
+        if options.vanilla {
+            bail!(
+                "Launching without mods is only supported when starting the game directly, not through Steam"
+            );
+        }
+        let appid = game
+            .game
+            .appid
+            .as_ref()
+            .context("No Steam app id known for this game")?;
+        log::info!("Launching '{}' through Steam", game.game.id);
+        crate::utils::open_url(&format!("steam://rungameid/{}", appid))?;
+        return Ok(Launch::Steam(record));
+    }
+
+    let exe = find_executable(game)?;
+    let mut command = if vanilla {
+        let mut command = Command::new(&exe);
+        // Doorstop reads these overrides on startup and skips loading BepInEx
+        command
+            .env("DOORSTOP_ENABLE", "FALSE")
+            .env_remove("LD_PRELOAD");
+        if cfg!(windows) {
+            command.args(["--doorstop-enable", "false"]);
+        }
+        command
+    } else if cfg!(windows) {
+        Command::new(&exe)
+    } else {
+        let script = game.install_path().join("run_bepinex.sh");
+        if !script.is_file() {
+            bail!(
+                "Failed to find BepInEx launch script at '{}'",
+                script.to_string_lossy()
+            );
+        }
+        let mut command = Command::new("sh");
+        command.arg(script).arg(&exe);
+        command
+    };
+
+    log::info!(
+        "Launching '{}' ({}) from '{}'",
+        game.game.id,
+        if vanilla { "vanilla" } else { "modded" },
+        exe.to_string_lossy()
+    );
+    let child = command
+        .current_dir(game.install_path())
+        .spawn()
+        .with_context(|| format!("Failed to start '{}'", exe.to_string_lossy()))?;
+
+    Ok(Launch::Process(RunningGame {
+        game_id: game.game.id.clone(),
+        child,
+        record,
+    }))
+}
+
+pub fn find_executable(game: &SetupGame) -> anyhow::Result<PathBuf> {
+    if let Some(exe) = &game.game.executable {
+        let path = game.install_path().join(exe);
+        if path.is_file() {
+            return Ok(path);
+        }
+        bail!("Game executable not found at '{}'", path.to_string_lossy());
+    }
+
+    let extensions: &[&str] = if cfg!(windows) {
+        &["exe"]
+    } else {
+        &["x86_64", "x86"]
+    };
+    let mut candidates = Vec::new();
+    for entry in game
+        .install_path()
+        .read_dir()
+        .context("Failed to list files in game directory")?
+    {
+        let path = entry
+            .context("Failed to list files in game directory")?
+            .path();
+        let is_executable = path
+            .extension()
+            .is_some_and(|ext| extensions.iter().any(|e| ext == *e));
+        let is_crash_handler = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("UnityCrashHandler"));
+        if path.is_file() && is_executable && !is_crash_handler {
+            candidates.push(path);
+        }
+    }
+
+    let by_name = candidates.iter().position(|path| {
+        path.file_stem()
+            .is_some_and(|stem| stem.to_string_lossy() == game.game.name)
+    });
+    match by_name {
+        Some(i) => Ok(candidates.swap_remove(i)),
+        None => candidates
+            .into_iter()
+            .next()
+            .context("Failed to find the game executable in the game directory"),
+    }
+}
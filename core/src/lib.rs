@@ -0,0 +1,60 @@
+//! Mod management for ytinu without any UI: setting up games, installing and enabling the BepInEx
+//! mod loader and installing mods from the metadata repository or local files.
+//!
+//! [`Manager`] loads and stores the state and runs actions on the selected game. Questions and
+//! errors for the user go through [`dialog`], where front-ends can install their own handler.
+//! Game directories and downloads are accessed through the [`Env`], which tests replace with an
+//! in-memory filesystem and local fixtures.
+
+pub mod adopt;
+pub mod data;
+pub mod dev_link;
+pub mod dialog;
+pub mod dotnet;
+pub mod fetch;
+pub mod fs;
+pub mod install_link;
+pub mod instance;
+pub mod launch;
+pub mod lint;
+pub mod local_mod;
+pub mod log_output;
+pub mod manager;
+pub mod migrations;
+pub mod pack;
+pub mod persist;
+pub mod plugin_config;
+pub mod plugins;
+pub mod process;
+pub mod signature;
+pub mod utils;
+
+use std::sync::Arc;
+
+pub use dialog::show_error;
+pub use manager::Manager;
+pub use utils::ErrorExt;
+
+/// Filesystem and network used to install mods and the mod loader
+#[derive(Clone)]
+pub struct Env {
+    pub fs: Arc<dyn fs::Fs>,
+    pub fetcher: Arc<dyn fetch::Fetcher>,
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Env {
+            fs: Arc::new(fs::RealFs),
+            fetcher: Arc::new(fetch::HttpFetcher),
+        }
+    }
+}
+
+pub static APP_VERSION: semver::Version = semver::Version {
+    major: 0,
+    minor: 1,
+    patch: 0,
+    build: Vec::new(),
+    pre: Vec::new(),
+};
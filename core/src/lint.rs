@@ -0,0 +1,329 @@
+//! `ytinu-cli lint-meta`: checks a checkout of the metadata repository for errors before publishing.
+//! The file formats are described by the JSON schemas in the `schema` directory.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::Path,
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::data::{GameMods, MetadataIn, Mod, STABLE_CHANNEL};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub file: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", severity, self.file, self.message)
+    }
+}
+
+struct Linter {
+    issues: Vec<LintIssue>,
+}
+
+impl Linter {
+    fn error(&mut self, file: &str, message: String) {
+        self.issues.push(LintIssue {
+            severity: Severity::Error,
+            file: file.to_string(),
+            message,
+        });
+    }
+
+    fn warning(&mut self, file: &str, message: String) {
+        self.issues.push(LintIssue {
+            severity: Severity::Warning,
+            file: file.to_string(),
+            message,
+        });
+    }
+
+    fn parse<T: DeserializeOwned>(&mut self, dir: &Path, file: &str) -> Option<T> {
+        let text = match std::fs::read_to_string(dir.join(file)) {
+            Ok(text) => text,
+            Err(error) => {
+                self.error(file, format!("Failed to read file: {}", error));
+                return None;
+            }
+        };
+        match serde_json::from_str(&text) {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.error(file, error.to_string());
+                None
+            }
+        }
+    }
+
+    fn check_duplicates<'a>(
+        &mut self,
+        file: &str,
+        kind: &str,
+        ids: impl Iterator<Item = &'a String>,
+    ) {
+        let mut seen = HashSet::new();
+        for id in ids {
+            if !seen.insert(id) {
+                self.error(file, format!("Duplicate {} id '{}'", kind, id));
+            }
+        }
+    }
+
+    fn check_url(&mut self, file: &str, context: &str, url: &str) -> Option<reqwest::Url> {
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if parsed.scheme() == "https" || parsed.scheme() == "http" => Some(parsed),
+            Ok(parsed) => {
+                self.error(
+                    file,
+                    format!(
+                        "{}: Unsupported URL scheme '{}' in '{}'",
+                        context,
+                        parsed.scheme(),
+                        url
+                    ),
+                );
+                None
+            }
+            Err(error) => {
+                self.error(
+                    file,
+                    format!("{}: Invalid URL '{}': {}", context, url, error),
+                );
+                None
+            }
+        }
+    }
+
+    fn check_mod(&mut self, file: &str, m: &Mod, app_version: &semver::Version) {
+        let context = format!("Mod '{}'", m.id);
+        if let Err(error) = crate::local_mod::validate_mod_id(&m.id) {
+            self.error(file, format!("{:#}", error));
+        }
+        if let Some(url) = self.check_url(file, &context, &m.download) {
+            let name = url
+                .path_segments()
+                .and_then(|mut ps| ps.next_back())
+                .unwrap_or_default();
+            if !name.ends_with(".dll") && !name.ends_with(".zip") {
+                self.error(
+                    file,
+                    format!(
+                        "{}: Unknown file type of download '{}'. Only .zip and .dll files are supported.",
+                        context, m.download
+                    ),
+                );
+            } else if name.ends_with(".dll") && m.extract_to_root {
+                self.warning(
+                    file,
+                    format!(
+                        "{}: extract_to_root has no effect for .dll downloads",
+                        context
+                    ),
+                );
+            }
+        }
+        for url in m.source.iter().chain(m.homepage.iter()) {
+            self.check_url(file, &context, url);
+        }
+        for path in m.files.iter().flatten() {
+            let invalid = Path::new(path).is_absolute()
+                || path.starts_with('/')
+                || path.split(['/', '\\']).any(|part| part == "..");
+            if invalid || path.is_empty() {
+                self.error(
+                    file,
+                    format!(
+                        "{}: File '{}' must be a path inside the game directory",
+                        context, path
+                    ),
+                );
+            }
+        }
+        if let Some(hash) = &m.hash {
+            if !is_checksum(hash) {
+                self.error(
+                    file,
+                    format!("{}: '{}' is not a blake2s checksum", context, hash),
+                );
+            }
+        }
+        if let Some(requirement) = &m.ytinu_version {
+            if !requirement.matches(app_version) {
+                self.error(
+                    file,
+                    format!(
+                        "{}: ytinu_version '{}' isn't satisfied by the latest ytinu release {}",
+                        context, requirement, app_version
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn is_checksum(hash: &str) -> bool {
+    hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Checks `meta.json` and all `games/<id>.json` files in a metadata directory.
+pub fn lint_meta(dir: &Path) -> Vec<LintIssue> {
+    let mut linter = Linter { issues: Vec::new() };
+    let meta_file = "meta.json";
+    let meta: MetadataIn = match linter.parse(dir, meta_file) {
+        Some(meta) => meta,
+        None => return linter.issues,
+    };
+    for warning in meta.warnings() {
+        linter.error(meta_file, warning);
+    }
+    let (games, mods) = (&meta.games.entries, &meta.mods.entries);
+
+    linter.check_duplicates(meta_file, "game", games.iter().map(|g| &g.id));
+    linter.check_duplicates(meta_file, "mod", mods.iter().map(|m| &m.id));
+    let messages = &meta.messages.entries;
+    linter.check_duplicates(meta_file, "message", messages.iter().map(|m| &m.id));
+
+    let releases = meta.releases();
+    for (channel, release) in &releases {
+        for (os, url) in &release.downloads {
+            let context = format!("Download of the {} release for '{}'", channel, os);
+            linter.check_url(meta_file, &context, url);
+            if !release.hashes.contains_key(os) {
+                linter.error(
+                    meta_file,
+                    format!(
+                        "The {} release for '{}' has no hash. ytinu refuses to install it.",
+                        channel, os
+                    ),
+                );
+            }
+        }
+        for (os, hash) in &release.hashes {
+            if !release.downloads.contains_key(os) {
+                linter.warning(
+                    meta_file,
+                    format!(
+                        "The {} release has a hash for '{}' but no download",
+                        channel, os
+                    ),
+                );
+            }
+            if !is_checksum(hash) {
+                linter.error(
+                    meta_file,
+                    format!(
+                        "The {} release for '{}': '{}' is not a blake2s checksum",
+                        channel, os, hash
+                    ),
+                );
+            }
+        }
+    }
+    if meta.format >= 2 {
+        let stable = releases.get(STABLE_CHANNEL);
+        let legacy_matches = meta.version.as_ref() == stable.map(|r| &r.version)
+            && Some(&meta.downloads) == stable.map(|r| &r.downloads);
+        if !legacy_matches {
+            linter.warning(
+                meta_file,
+                "'version' and 'downloads' should match the stable release for older ytinu versions"
+                    .to_string(),
+            );
+        }
+    }
+    let latest = releases
+        .get(STABLE_CHANNEL)
+        .map(|release| release.version.clone())
+        .unwrap_or_else(|| semver::Version::new(0, 0, 0));
+
+    for message in messages {
+        if !message.version.matches(&latest) {
+            linter.warning(
+                meta_file,
+                format!(
+                    "Message '{}' isn't shown by the latest ytinu release {} (version '{}')",
+                    message.id, latest, message.version
+                ),
+            );
+        }
+    }
+    for m in mods {
+        linter.check_mod(meta_file, m, &latest);
+    }
+    let general_mods: HashSet<&String> = mods.iter().map(|m| &m.id).collect();
+
+    let mut game_files = HashMap::new();
+    for game in games {
+        let file = format!("games/{}.json", game.id);
+        if !dir.join(&file).is_file() {
+            linter.warning(
+                meta_file,
+                format!("Game '{}' has no mod file '{}'", game.id, file),
+            );
+        } else if let Some(game_mods) = linter.parse::<GameMods>(dir, &file) {
+            for warning in game_mods.mods.warnings("mod") {
+                linter.error(&file, warning);
+            }
+            let game_mods = game_mods.mods.entries;
+            linter.check_duplicates(&file, "mod", game_mods.iter().map(|m| &m.id));
+            for m in &game_mods {
+                linter.check_mod(&file, m, &latest);
+                if general_mods.contains(&m.id) {
+                    linter.warning(
+                        &file,
+                        format!("Mod '{}' overrides the general mod with the same id", m.id),
+                    );
+                }
+            }
+            game_files.insert(game.id.clone(), game_mods);
+        }
+
+        for id in &game.recommended_mods {
+            let in_game_mods = game_files
+                .get(&game.id)
+                .is_some_and(|mods: &Vec<Mod>| mods.iter().any(|m| &m.id == id));
+            if !in_game_mods && !general_mods.contains(id) {
+                linter.error(
+                    meta_file,
+                    format!(
+                        "Game '{}' recommends mod '{}' which doesn't exist",
+                        game.id, id
+                    ),
+                );
+            }
+        }
+    }
+
+    if let Ok(entries) = dir.join("games").read_dir() {
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_string());
+            let known = stem.is_some_and(|stem| games.iter().any(|g| g.id == stem));
+            if path.extension().is_some_and(|ext| ext == "json") && !known {
+                linter.warning(
+                    &format!("games/{}", entry.file_name().to_string_lossy()),
+                    "File doesn't belong to any game in meta.json".to_string(),
+                );
+            }
+        }
+    }
+
+    linter.issues
+}
@@ -0,0 +1,135 @@
+//! Installing mods from local `.zip` or `.dll` files that aren't published in any metadata repository.
+
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+
+use anyhow::{bail, ensure, Context};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{data::Mod, dotnet::AssemblyInfo, fs::RealFs};
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct LocalModRequest {
+    pub path: String,
+    /// Mod id to install the file as. Detected from the plugin metadata if not set.
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+/// Creates a `Mod` entry for a local file. Uses the id of a known mod if the plugin
+/// belongs to it so a later install from the repository replaces the local build.
+pub fn local_mod(
+    path: &Path,
+    id: Option<String>,
+    known_mods: &HashMap<String, Mod>,
+) -> anyhow::Result<Mod> {
+    ensure!(
+        path.is_file(),
+        "File not found: '{}'",
+        path.to_string_lossy()
+    );
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("Invalid path: '{}'", path.to_string_lossy()))?;
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    let assembly = match extension.as_deref() {
+        Some("dll") => crate::dotnet::read_assembly(&RealFs, &path).ok(),
+        Some("zip") => find_zipped_assembly(&path)?,
+        _ => bail!(
+            "Unrecognized file type: '{}'\nValid types are only .zip and .dll",
+            path.to_string_lossy()
+        ),
+    };
+
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let matched = assembly
+        .as_ref()
+        .and_then(|assembly| crate::plugins::match_known_mod(&path, assembly, known_mods))
+        .and_then(|id| known_mods.get(&id));
+    let plugin = assembly.as_ref().and_then(|a| a.plugins.first());
+
+    let id = match id {
+        Some(id) => id,
+        None => match matched {
+            Some(m) => m.id.clone(),
+            None => assembly
+                .as_ref()
+                .and_then(|a| a.name.clone())
+                .unwrap_or_else(|| stem.clone()),
+        },
+    };
+    validate_mod_id(&id)?;
+
+    let name = match (matched.filter(|m| m.id == id), plugin) {
+        (Some(m), _) => m.name.clone(),
+        (None, Some(plugin)) => plugin.name.clone(),
+        (None, None) => id.clone(),
+    };
+    let version = plugin
+        .and_then(|plugin| crate::plugins::parse_version(&plugin.version))
+        .unwrap_or_else(|| semver::Version::new(0, 0, 0));
+    let download = reqwest::Url::from_file_path(&path)
+        .map_err(|()| anyhow::anyhow!("Invalid path: '{}'", path.to_string_lossy()))?
+        .to_string();
+
+    Ok(Mod {
+        description: Some(format!("Installed from {}", path.to_string_lossy())),
+        id,
+        name,
+        download,
+        version,
+        extract_to_root: false,
+        files: None,
+        hash: crate::utils::checksum(&RealFs, &path).ok(),
+        size: std::fs::metadata(&path).ok().map(|m| m.len()),
+        local: true,
+        dev_mod: false,
+        source: None,
+        homepage: None,
+        ytinu_version: None,
+    })
+}
+
+/// Reads the first plugin assembly in a zip file.
+fn find_zipped_assembly(path: &Path) -> anyhow::Result<Option<AssemblyInfo>> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to read '{}'", path.to_string_lossy()))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("Failed to read '{}'", path.to_string_lossy()))?;
+    let mut fallback = None;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if !entry.name().to_lowercase().ends_with(".dll") {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        if let Ok(assembly) = crate::dotnet::parse_assembly(&bytes) {
+            if !assembly.plugins.is_empty() {
+                return Ok(Some(assembly));
+            }
+            fallback.get_or_insert(assembly);
+        }
+    }
+    Ok(fallback)
+}
+
+/// Mod ids are used as file and directory names in the plugins directory.
+pub fn validate_mod_id(id: &str) -> anyhow::Result<()> {
+    ensure!(
+        !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+            && id != "."
+            && id != "..",
+        "Invalid mod id: '{}'",
+        id
+    );
+    Ok(())
+}
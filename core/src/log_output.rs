@@ -0,0 +1,211 @@
+//! Parser for the `BepInEx/LogOutput.log` file.
+//!
+//! Every log line has the form `[Level  :Source] message`. Lines that don't start with such a
+//! header (e.g. stack traces) belong to the previous entry.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{ensure, Context};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{data::SetupGame, dotnet::AssemblyInfo, fs::Fs, utils::normalize_name};
+
+/// Only the end of large log files is read.
+static MAX_READ_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct LogEntry {
+    pub level: String,
+    pub source: String,
+    pub message: String,
+    pub exception: bool,
+    /// Id of the installed mod this entry was attributed to
+    pub mod_id: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct GameLog {
+    pub entries: Vec<LogEntry>,
+    /// Number of exceptions per attributed mod id
+    pub exceptions: HashMap<String, usize>,
+    /// Number of exceptions that couldn't be attributed to any installed mod
+    pub unattributed_exceptions: usize,
+}
+
+pub fn read(fs: &dyn Fs, game: &SetupGame, limit: usize) -> anyhow::Result<GameLog> {
+    let path = game.install_path().join("BepInEx").join("LogOutput.log");
+    let text = read_tail(fs, &path)?;
+    let mut entries = parse(&text);
+    if entries.len() > limit {
+        entries.drain(..entries.len() - limit);
+    }
+
+    let owners = ModOwners::new(fs, game);
+    let mut exceptions = HashMap::new();
+    let mut unattributed_exceptions = 0;
+    for entry in &mut entries {
+        entry.mod_id = owners.attribute(entry);
+        if entry.exception {
+            match &entry.mod_id {
+                Some(id) => *exceptions.entry(id.clone()).or_default() += 1,
+                None => unattributed_exceptions += 1,
+            }
+        }
+    }
+
+    Ok(GameLog {
+        entries,
+        exceptions,
+        unattributed_exceptions,
+    })
+}
+
+fn read_tail(fs: &dyn Fs, path: &Path) -> anyhow::Result<String> {
+    ensure!(
+        fs.is_file(path),
+        "Failed to open '{}'. You might have to start the game once to generate it.",
+        path.to_string_lossy()
+    );
+    let (bytes, cut_off) = fs
+        .read_end(path, MAX_READ_BYTES)
+        .context("Failed to read log file")?;
+    let text = String::from_utf8_lossy(&bytes).into_owned();
+    if cut_off {
+        // Skip the partial first line
+        return Ok(text
+            .split_once('\n')
+            .map(|(_, rest)| rest.to_string())
+            .unwrap_or_default());
+    }
+    Ok(text)
+}
+
+pub fn parse(text: &str) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for line in text.lines() {
+        if let Some((level, source, message)) = parse_header(line) {
+            entries.push(LogEntry {
+                level: level.to_string(),
+                source: source.to_string(),
+                exception: is_exception(message),
+                message: message.to_string(),
+                mod_id: None,
+            });
+        } else if let Some(entry) = entries.last_mut() {
+            if line.trim_start().starts_with("at ") || is_exception(line) {
+                entry.exception = true;
+            }
+            entry.message.push('\n');
+            entry.message.push_str(line);
+        }
+    }
+    entries
+}
+
+fn parse_header(line: &str) -> Option<(&str, &str, &str)> {
+    let rest = line.strip_prefix('[')?;
+    let (header, message) = rest.split_once(']')?;
+    let (level, source) = header.split_once(':')?;
+    let level = level.trim();
+    match level {
+        "Fatal" | "Error" | "Warning" | "Message" | "Info" | "Debug" => {
+            Some((level, source.trim(), message.trim_start()))
+        }
+        _ => None,
+    }
+}
+
+fn is_exception(message: &str) -> bool {
+    message.split_whitespace().next().is_some_and(|word| {
+        word.trim_end_matches(':').ends_with("Exception") && word.ends_with(':')
+    })
+}
+
+/// Lookup from names appearing in the log to installed mods.
+struct ModOwners {
+    keys: Vec<(String, String)>,
+}
+
+impl ModOwners {
+    /// BepInEx logs plugins under their name and stack traces contain the namespace of their
+    /// types, so the plugin metadata of the assemblies is matched first. Names and ids of the
+    /// mods and the names of their DLLs are fallbacks for assemblies without plugins.
+    fn new(fs: &dyn Fs, game: &SetupGame) -> Self {
+        let mut keys = Vec::new();
+        let mut fallback_keys = Vec::new();
+        for (id, m) in &game.mods {
+            fallback_keys.push((normalize_name(id), id.clone()));
+            fallback_keys.push((normalize_name(&m.m.name), id.clone()));
+            for (stem, assembly) in mod_assemblies(fs, game, id) {
+                fallback_keys.push((normalize_name(&stem), id.clone()));
+                for plugin in assembly.iter().flat_map(|a| &a.plugins) {
+                    keys.push((normalize_name(&plugin.name), id.clone()));
+                    keys.push((normalize_name(&plugin.guid), id.clone()));
+                    if let Some((namespace, _)) = plugin.type_name.rsplit_once('.') {
+                        keys.push((normalize_name(namespace), id.clone()));
+                    }
+                }
+            }
+        }
+        keys.extend(fallback_keys);
+        keys.retain(|(key, _)| !key.is_empty());
+        Self { keys }
+    }
+
+    fn find(&self, name: &str) -> Option<&String> {
+        let name = normalize_name(name);
+        self.keys
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, id)| id)
+    }
+
+    fn attribute(&self, entry: &LogEntry) -> Option<String> {
+        if let Some(id) = self.find(&entry.source) {
+            return Some(id.clone());
+        }
+        if !entry.exception {
+            return None;
+        }
+        // Attribute exceptions logged by Unity or BepInEx by their stack trace,
+        // assuming mods use their assembly name as root namespace.
+        // Mono frames look like `  at Ns.Type.Method () [0x00000] in <...>:0`,
+        // Unity frames like `Ns.Type.Method () (at <...>:0)`.
+        entry
+            .message
+            .lines()
+            .skip(1)
+            .map(|line| line.trim_start().trim_start_matches("at "))
+            .flat_map(|frame| {
+                let path = frame.split([' ', '(']).next().unwrap_or("");
+                let mut prefixes = Vec::new();
+                for (i, c) in path.char_indices() {
+                    if c == '.' {
+                        prefixes.push(&path[..i]);
+                    }
+                }
+                prefixes
+            })
+            .find_map(|prefix| self.find(prefix))
+            .cloned()
+    }
+}
+
+/// File stems and, if readable, assembly metadata of the DLLs that belong to an installed mod
+fn mod_assemblies(fs: &dyn Fs, game: &SetupGame, id: &str) -> Vec<(String, Option<AssemblyInfo>)> {
+    let mut dlls = Vec::new();
+    for path in game.mod_paths(id) {
+        if fs.is_dir(&path) {
+            dlls.extend(crate::plugins::find_dlls(fs, path));
+        } else if path.extension().is_some_and(|ext| ext == "dll") {
+            dlls.push(path);
+        }
+    }
+    dlls.iter()
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy().to_string();
+            Some((stem, crate::dotnet::read_assembly(fs, path).ok()))
+        })
+        .collect()
+}
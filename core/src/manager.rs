@@ -0,0 +1,846 @@
+//! Loading and storing the state and running actions on the set up games.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, ensure, Context};
+use app_dirs::AppDataType;
+use semver::Version;
+
+use crate::{
+    adopt::UnmanagedFile,
+    data::*,
+    dev_link::{LinkMode, LinkedMod},
+    dialog,
+    fetch::Fetcher,
+    fs::{Fs, RealFs},
+    install_link::InstallLink,
+    launch::{Launch, LaunchOptions, RunningGame},
+    migrations::STATE_MIGRATIONS,
+    persist::{JsonFile, Loaded},
+    Env, APP_VERSION,
+};
+
+/// Number of previous versions of `data.json` that are kept
+pub static DATA_BACKUPS: usize = 5;
+
+/// How long the running games shown in the UI are reused. Finding them scans all processes.
+static RUNNING_GAMES_CACHE_TIME: Duration = Duration::from_secs(5);
+/// How long the unmanaged plugins shown in the UI are reused unless plugin files are added or
+/// removed or the state changes. Finding them parses and hashes every plugin assembly.
+static UNMANAGED_CACHE_TIME: Duration = Duration::from_secs(30);
+
+pub static METADATA_URL: &str =
+    "https://raw.githubusercontent.com/ytinu-mods/meta/master/meta.json";
+pub static GAME_MODS_URL_BASE: &str =
+    "https://raw.githubusercontent.com/ytinu-mods/meta/master/games/";
+
+#[cfg(unix)]
+pub static BEP_IN_EX_DOWNLOAD_URL: &str =
+    "https://github.com/BepInEx/BepInEx/releases/download/v5.4.4/BepInEx_unix_5.4.4.0.zip";
+#[cfg(windows)]
+pub static BEP_IN_EX_DOWNLOAD_URL: &str =
+    "https://github.com/BepInEx/BepInEx/releases/download/v5.4.4/BepInEx_x64_5.4.4.0.zip";
+pub static BEP_IN_EX_FILE_NAME: &str = "BepInEx_v5.4.4.0.zip";
+pub static BEP_IN_EX_VERSION: Version = Version {
+    major: 5,
+    minor: 4,
+    patch: 4,
+    build: Vec::new(),
+    pre: Vec::new(),
+};
+
+/// Stored state, config and metadata of ytinu. Changes to the state are stored immediately, the
+/// config is stored when the manager is dropped.
+pub struct Manager {
+    env: Env,
+    data_file: JsonFile,
+    metadata: Option<Metadata>,
+    state: State,
+    config: Config,
+    running_games: Vec<RunningGame>,
+    /// Last seen build output of linked mods by game and mod id
+    link_snapshots: HashMap<(String, String), crate::dev_link::Snapshot>,
+    /// Linked mods whose redeploy waits for the game to exit
+    deferred_links: HashSet<(String, String)>,
+    running_game_ids: Option<Cached<(), Vec<String>>>,
+    unmanaged: Option<Cached<PluginsKey, Vec<UnmanagedFile>>>,
+}
+
+/// Selected game and its plugin assemblies
+type PluginsKey = (String, Vec<PathBuf>);
+
+/// Result of an expensive check that is reused until it expires or its key changes
+struct Cached<K, T> {
+    key: K,
+    created: Instant,
+    value: T,
+}
+
+impl<K: PartialEq, T: Clone> Cached<K, T> {
+    fn get_or_update(
+        cache: &mut Option<Self>,
+        key: K,
+        max_age: Duration,
+        update: impl FnOnce() -> T,
+    ) -> T {
+        match cache {
+            Some(cached) if cached.key == key && cached.created.elapsed() < max_age => {
+                cached.value.clone()
+            }
+            _ => {
+                let value = update();
+                *cache = Some(Cached {
+                    key,
+                    created: Instant::now(),
+                    value: value.clone(),
+                });
+                value
+            }
+        }
+    }
+}
+
+impl Manager {
+    /// Loads the stored state and config. Doesn't fetch any metadata.
+    /// Fails if the state or config file exists but can't be used, so it isn't overwritten.
+    pub fn load() -> anyhow::Result<Self> {
+        let (data_file, state) = load_state()?;
+        Ok(Manager {
+            env: Env::default(),
+            data_file,
+            metadata: None,
+            state,
+            config: Config::load()?,
+            running_games: Vec::new(),
+            link_snapshots: HashMap::new(),
+            deferred_links: HashSet::new(),
+            running_game_ids: None,
+            unmanaged: None,
+        })
+    }
+
+    /// Fetches the metadata of all games and mods
+    pub fn fetch_metadata(&mut self) {
+        self.metadata = fetch_metadata(&*self.env.fetcher);
+        self.unmanaged = None;
+    }
+
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// State for the UI. The running games and unmanaged plugins are cached because they are
+    /// expensive to find.
+    pub fn state_out(&mut self) -> StateOut {
+        let state = &self.state;
+        let running_games = Cached::get_or_update(
+            &mut self.running_game_ids,
+            (),
+            RUNNING_GAMES_CACHE_TIME,
+            || {
+                state
+                    .games
+                    .iter()
+                    .filter(|(_, game)| game.is_running())
+                    .map(|(id, _)| id.clone())
+                    .collect()
+            },
+        );
+        let unmanaged = match (&state.selected_game, state.current_game()) {
+            (Some(id), Some(game)) => {
+                let fs = &*self.env.fs;
+                let dlls = crate::plugins::find_dlls(fs, game.plugins_path());
+                let known_mods = self.known_mods();
+                Cached::get_or_update(
+                    &mut self.unmanaged,
+                    (id.clone(), dlls),
+                    UNMANAGED_CACHE_TIME,
+                    || crate::adopt::find_unmanaged(fs, game, &known_mods),
+                )
+            }
+            _ => Vec::new(),
+        };
+        StateOut::new(&self.state, running_games, unmanaged)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+        self.config.store();
+    }
+
+    pub fn current_game(&self) -> anyhow::Result<&SetupGame> {
+        Ok(self
+            .state
+            .current_game()
+            .ok_or(ActionError::NoGameSelected)?)
+    }
+
+    /// Runs an action on the selected game and stores the state even if the action failed
+    /// halfway, e.g. after some files were already removed.
+    pub fn modify_current_game<T>(
+        &mut self,
+        action: impl FnOnce(&mut SetupGame) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let game = self
+            .state
+            .current_game_mut()
+            .ok_or(ActionError::NoGameSelected)?;
+        let result = action(game);
+        self.store_state();
+        result
+    }
+
+    fn ensure_installed(&self, mod_id: &str) -> anyhow::Result<()> {
+        ensure!(
+            self.current_game()?.mods.contains_key(mod_id),
+            ActionError::NotFound(format!("'{}' is not installed", mod_id))
+        );
+        Ok(())
+    }
+
+    /// Installs a mod from the metadata into the selected game.
+    pub fn install_mod(&mut self, mod_id: &str) -> anyhow::Result<()> {
+        let m = self
+            .get_mod(mod_id)
+            .cloned()
+            .ok_or_else(|| ActionError::NotFound(format!("No mod with id '{}' found", mod_id)))?;
+        ensure!(
+            !self.current_game()?.mods.contains_key(mod_id) || m.local,
+            ActionError::Conflict(format!("'{}' is already installed", m.name))
+        );
+        let env = self.env.clone();
+        self.modify_current_game(|game| game.install_mod(&env, m))
+    }
+
+    /// Checks that a link from a website can be installed into the selected game and returns the
+    /// mod it refers to. Doesn't install anything.
+    pub fn check_install_link(&self, link: &InstallLink) -> anyhow::Result<&Mod> {
+        let game = self.current_game()?;
+        ensure!(
+            game.game.id == link.game,
+            ActionError::InvalidInput(format!(
+                "The link is for the game '{}', but {} is selected",
+                link.game, game.game.name
+            ))
+        );
+        let m = self.get_mod(&link.mod_id).ok_or_else(|| {
+            ActionError::NotFound(format!("No mod with id '{}' found", link.mod_id))
+        })?;
+        ensure!(
+            !game.mods.contains_key(&m.id),
+            ActionError::Conflict(format!("'{}' is already installed", m.name))
+        );
+        if let Some(version) = &link.version {
+            ensure!(
+                *version == m.version,
+                ActionError::NotFound(format!(
+                    "Version {} of '{}' isn't available. The latest version is {}.",
+                    version, m.name, m.version
+                ))
+            );
+        }
+        Ok(m)
+    }
+
+    /// Updates an installed mod. A local build is replaced with the version from the metadata.
+    pub fn update_mod(&mut self, mod_id: &str) -> anyhow::Result<()> {
+        self.ensure_installed(mod_id)?;
+        let repo_mod = self.get_mod(mod_id).cloned();
+        let env = self.env.clone();
+        self.modify_current_game(|game| match (game.mods.get(mod_id), repo_mod) {
+            // Upgrade a local build to the version from the repository
+            (Some(installed), Some(m)) if installed.m.local => game.install_mod(&env, m),
+            _ => game.update_mod(&env, mod_id),
+        })
+    }
+
+    pub fn remove_mod(&mut self, mod_id: &str) -> anyhow::Result<()> {
+        self.ensure_installed(mod_id)?;
+        let env = self.env.clone();
+        self.modify_current_game(|game| game.remove_mod(&env, mod_id))
+    }
+
+    /// Installs a mod from a local `.zip` or `.dll` file into the selected game.
+    pub fn install_local(&mut self, path: &Path, id: Option<String>) -> anyhow::Result<()> {
+        let m = crate::local_mod::local_mod(path, id, &self.known_mods())?;
+        log::info!("Installing '{}' v{} from '{}'", m.id, m.version, m.download);
+        let env = self.env.clone();
+        self.modify_current_game(|game| game.install_mod(&env, m))
+    }
+
+    pub fn install_modloader(&mut self) -> anyhow::Result<()> {
+        let env = self.env.clone();
+        self.modify_current_game(|game| game.install_modloader(&env))
+    }
+
+    /// Removes the mod loader with all mods and configuration after asking the user.
+    pub fn remove_modloader(&mut self) -> anyhow::Result<()> {
+        let env = self.env.clone();
+        self.modify_current_game(|game| game.remove_modloader(&env))
+    }
+
+    pub fn set_modloader_enabled(&mut self, enabled: bool) -> anyhow::Result<()> {
+        let env = self.env.clone();
+        self.modify_current_game(|game| game.set_modloader_enabled(&env, enabled))
+    }
+
+    /// Links a local build directory to the selected game and deploys it. Returns the mod id.
+    pub fn link_mod(
+        &mut self,
+        source: &Path,
+        id: Option<String>,
+        mode: LinkMode,
+        scripts: bool,
+    ) -> anyhow::Result<String> {
+        let mut link = LinkedMod::new(source, mode, scripts)?;
+        let id = match id {
+            Some(id) => id,
+            None => self.detect_linked_mod_id(link.source()),
+        };
+        crate::local_mod::validate_mod_id(&id)?;
+
+        let game_id = self
+            .state
+            .selected_game
+            .clone()
+            .ok_or(ActionError::NoGameSelected)?;
+        let game = self
+            .state
+            .current_game_mut()
+            .ok_or(ActionError::NoGameSelected)?;
+        ensure!(
+            !game.mods.contains_key(&id),
+            ActionError::Conflict(format!(
+                "'{}' is already installed. Uninstall it before linking a build directory.",
+                id
+            ))
+        );
+        if let Some((other, _)) = game
+            .linked_mods
+            .iter()
+            .find(|(other, l)| **other != id && l.source == link.source)
+        {
+            bail!(ActionError::Conflict(format!(
+                "'{}' is already linked as '{}'",
+                link.source, other
+            )));
+        }
+        game.ensure_not_running()?;
+        if let Some(mut old) = game.linked_mods.remove(&id) {
+            old.undeploy(game.install_path())?;
+        }
+        link.deploy(game.install_path(), &id)?;
+        self.link_snapshots.insert(
+            (game_id, id.clone()),
+            crate::dev_link::snapshot(link.source()),
+        );
+        game.linked_mods.insert(id.clone(), link);
+        self.store_state();
+        Ok(id)
+    }
+
+    pub fn unlink_mod(&mut self, id: &str) -> anyhow::Result<()> {
+        let game = self
+            .state
+            .current_game_mut()
+            .ok_or(ActionError::NoGameSelected)?;
+        ensure!(
+            game.linked_mods.contains_key(id),
+            ActionError::NotFound(format!("'{}' is not linked", id))
+        );
+        game.ensure_not_running()?;
+        let install_path = PathBuf::from(&game.install_path);
+        let link = game.linked_mods.get_mut(id).unwrap();
+        link.undeploy(&install_path)?;
+        game.linked_mods.remove(id);
+        self.store_state();
+        Ok(())
+    }
+
+    /// Uses the name of a known mod or plugin assembly in the directory as id for a linked mod,
+    /// falling back to the directory name. Build directories are always on the real filesystem,
+    /// like in [`crate::dev_link`].
+    fn detect_linked_mod_id(&self, source: &Path) -> String {
+        let known_mods = self.known_mods();
+        let assemblies = RealFs
+            .read_dir(source)
+            .into_iter()
+            .flatten()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "dll"))
+            .filter_map(|path| Some((crate::dotnet::read_assembly(&RealFs, &path).ok()?, path)))
+            .filter(|(assembly, _)| !assembly.plugins.is_empty());
+        let mut fallback = None;
+        for (assembly, path) in assemblies {
+            if let Some(id) = crate::plugins::match_known_mod(&path, &assembly, &known_mods) {
+                return id;
+            }
+            fallback = fallback.or(assembly.name);
+        }
+        fallback.unwrap_or_else(|| {
+            source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Redeploys linked mods whose build output changed since the last check.
+    /// Mods in the plugins directory are only redeployed while the game isn't running.
+    /// ScriptEngine scripts are redeployed right away to be reloaded by the running game.
+    pub fn redeploy_changed_links(&mut self) {
+        let mut changed = false;
+        for (game_id, game) in self.state.games.iter_mut() {
+            let install_path = PathBuf::from(&game.install_path);
+            let mut running = None;
+            for (id, link) in game.linked_mods.iter_mut() {
+                if !link.needs_watching() {
+                    continue;
+                }
+                let snapshot = crate::dev_link::snapshot(link.source());
+                let key = (game_id.clone(), id.clone());
+                if self.link_snapshots.get(&key) == Some(&snapshot) {
+                    continue;
+                }
+                if !link.scripts
+                    && *running.get_or_insert_with(|| crate::process::is_running_in(&install_path))
+                {
+                    if self.deferred_links.insert(key) {
+                        log::info!(
+                            "Linked mod '{}' changed. It is redeployed once the game has exited.",
+                            id
+                        );
+                    }
+                    continue;
+                }
+                self.deferred_links.remove(&key);
+                // Errors are only logged once per change, e.g. if the game locks the files
+                if let Err(error) = link.deploy(&install_path, id) {
+                    log::warn!("Failed to redeploy linked mod '{}': {:#}", id, error);
+                }
+                self.link_snapshots.insert(key, snapshot);
+                changed = true;
+            }
+        }
+        if changed {
+            self.store_state();
+        }
+    }
+
+    /// Lets ytinu manage the manually installed files of a known mod.
+    pub fn adopt_mod(&mut self, mod_id: &str) -> anyhow::Result<()> {
+        let known_mods = self.known_mods();
+        let m = known_mods
+            .get(mod_id)
+            .ok_or_else(|| ActionError::NotFound(format!("No mod with id '{}' found", mod_id)))?;
+        let game = self
+            .state
+            .current_game_mut()
+            .ok_or(ActionError::NoGameSelected)?;
+        let unmanaged = crate::adopt::find_unmanaged(&*self.env.fs, game, &known_mods);
+        ensure!(
+            crate::adopt::adopt(&*self.env.fs, game, m.clone(), &unmanaged),
+            ActionError::NotFound(format!("No unmanaged files found for '{}'", m.name))
+        );
+        game.declined_adoptions.remove(mod_id);
+        self.store_state();
+        Ok(())
+    }
+
+    /// Asks the user whether manually installed mods of the selected game should be managed by
+    /// ytinu. Mods the user declined once are not asked for again.
+    fn offer_adoption(&mut self) {
+        let known_mods = self.known_mods();
+        let game = match self.state.current_game_mut() {
+            Some(game) => game,
+            None => return,
+        };
+        let unmanaged = crate::adopt::find_unmanaged(&*self.env.fs, game, &known_mods);
+        let mut candidates: Vec<&Mod> = Vec::new();
+        for file in &unmanaged {
+            if let Some(m) = file.matched_mod.as_ref().and_then(|id| known_mods.get(id)) {
+                if !game.declined_adoptions.contains(&m.id)
+                    && !candidates.iter().any(|c| c.id == m.id)
+                {
+                    candidates.push(m);
+                }
+            }
+        }
+        if candidates.is_empty() {
+            return;
+        }
+
+        let list: Vec<_> = candidates
+            .iter()
+            .map(|m| {
+                let files: Vec<_> = unmanaged
+                    .iter()
+                    .filter(|file| file.matched_mod.as_ref() == Some(&m.id))
+                    .map(|file| file.path.as_str())
+                    .collect();
+                format!("- {} ({})", m.name, files.join(", "))
+            })
+            .collect();
+        let adopt = dialog::confirm(
+            "Manually installed mods found",
+            &format!(
+                "The following mods were installed without ytinu:\n\
+                 {}\n\
+                 \n\
+                 Do you want ytinu to manage them so they can be updated and removed from here?",
+                list.join("\n")
+            ),
+            MessageIcon::Question,
+            true,
+        );
+        for m in candidates {
+            if adopt {
+                crate::adopt::adopt(&*self.env.fs, game, m.clone(), &unmanaged);
+            } else {
+                game.declined_adoptions.insert(m.id.clone());
+            }
+        }
+        self.store_state();
+    }
+
+    /// Launches the selected game. Direct launches are tracked until the game exits.
+    pub fn launch_game(&mut self, options: LaunchOptions) -> anyhow::Result<()> {
+        let game = self
+            .state
+            .current_game_mut()
+            .ok_or(ActionError::NoGameSelected)?;
+        match crate::launch::launch(game, options)? {
+            Launch::Steam(record) => {
+                game.record_launch(record);
+                self.store_state();
+            }
+            Launch::Process(running) => self.running_games.push(running),
+        }
+        Ok(())
+    }
+
+    /// Launches the selected game and blocks until it exits. Returns the exit code if known.
+    pub fn launch_game_and_wait(&mut self, options: LaunchOptions) -> anyhow::Result<Option<i32>> {
+        let game = self
+            .state
+            .current_game_mut()
+            .ok_or(ActionError::NoGameSelected)?;
+        let record = match crate::launch::launch(game, options)? {
+            Launch::Steam(record) => record,
+            Launch::Process(running) => running.wait(),
+        };
+        let exit_code = record.exit_code;
+        game.record_launch(record);
+        self.store_state();
+        Ok(exit_code)
+    }
+
+    /// Records the launches of all tracked game processes that have exited since the last check.
+    pub fn reap_games(&mut self) {
+        let mut finished = Vec::new();
+        self.running_games
+            .retain_mut(|running| match running.try_finish() {
+                Some(record) => {
+                    finished.push((running.game_id.clone(), record));
+                    false
+                }
+                None => true,
+            });
+        if finished.is_empty() {
+            return;
+        }
+        for (game_id, record) in finished {
+            if let Some(game) = self.state.games.get_mut(&game_id) {
+                game.record_launch(record);
+            }
+        }
+        self.store_state();
+    }
+
+    /// Newest release on the configured update channel if it is newer than the running version
+    pub fn available_update(&self) -> Option<&AppRelease> {
+        self.metadata
+            .as_ref()?
+            .latest_release(self.config.update_channel)
+            .filter(|release| release.version > APP_VERSION)
+    }
+
+    /// Metadata for the UI with the update state of the configured update channel
+    pub fn metadata_out(&self) -> Option<MetadataOut> {
+        let meta = self.metadata.as_ref()?;
+        let latest = meta.latest_release(self.config.update_channel);
+        let update = self
+            .available_update()
+            .is_some_and(|release| !self.is_skipped(release));
+        Some(MetadataOut::new(meta, latest, update))
+    }
+
+    pub fn is_skipped(&self, release: &AppRelease) -> bool {
+        self.config.skipped_version.as_ref() == Some(&release.version)
+    }
+
+    pub fn update_info(&self) -> UpdateInfo {
+        let release = self.available_update();
+        let notes = match (&self.metadata, release) {
+            (Some(meta), Some(release)) => meta.release_notes(&APP_VERSION, release),
+            _ => Vec::new(),
+        };
+        UpdateInfo {
+            installed: APP_VERSION.clone(),
+            channel: self.config.update_channel,
+            skipped: release.is_some_and(|release| self.is_skipped(release)),
+            release: release.cloned(),
+            notes,
+        }
+    }
+
+    pub fn skip_update(&mut self, version: Option<Version>) {
+        match &version {
+            Some(version) => log::info!("Skipping ytinu version {}", version),
+            None => log::info!("No longer skipping any ytinu version"),
+        }
+        self.config.skipped_version = version;
+        self.config.store();
+    }
+
+    /// All general mods and the mods for the selected game from the loaded metadata
+    pub fn known_mods(&self) -> HashMap<String, Mod> {
+        let mut mods = HashMap::new();
+        if let Some(meta) = &self.metadata {
+            mods.extend(meta.mods.clone());
+            if let Some(game_mods) = self
+                .state
+                .selected_game
+                .as_ref()
+                .and_then(|id| meta.game_mods.get(id))
+            {
+                mods.extend(game_mods.clone());
+            }
+        }
+        mods
+    }
+
+    pub fn get_mod(&self, id: &str) -> Option<&Mod> {
+        if let Some(m) = self.metadata.as_ref()?.mods.get(id) {
+            return Some(m);
+        }
+        self.metadata
+            .as_ref()?
+            .game_mods
+            .get(self.state.selected_game.as_ref()?)
+            .and_then(|mods| mods.get(id))
+    }
+
+    pub fn update_install_path(&mut self, install_path: String) -> anyhow::Result<()> {
+        let game = self.state.games.get_mut("Desperados3").ok_or_else(|| {
+            ActionError::NotFound("Desperados III is not yet configured".to_string())
+        })?;
+
+        ensure!(
+            self.env.fs.exists(Path::new(&install_path)),
+            ActionError::InvalidInput("Path is invalid or doesn't exist".to_string())
+        );
+        game.install_path = install_path;
+        self.store_state();
+        Ok(())
+    }
+
+    /// Sets up Desperados III from its install path and selects it.
+    pub fn add_game(&mut self, install_path: String) -> anyhow::Result<()> {
+        ensure!(
+            self.state.games.is_empty(),
+            ActionError::Conflict("Desperados III is already configured".to_string())
+        );
+        ensure!(
+            self.env.fs.exists(Path::new(&install_path)),
+            ActionError::InvalidInput("Path is invalid or doesn't exist".to_string())
+        );
+
+        let meta = self
+            .metadata
+            .as_ref()
+            .context("No metadata loaded. Can not add new game.")?;
+        let game = meta
+            .games
+            .get("Desperados3")
+            .context("No metadata for the game Desperados3 loaded.")?;
+        let mut new_game = SetupGame {
+            game: game.clone(),
+            install_path,
+            mods: HashMap::new(),
+            bep_in_ex: None,
+            launches: Vec::new(),
+            declined_adoptions: HashSet::new(),
+            linked_mods: HashMap::new(),
+        };
+        new_game.update_modloader_status(&self.env);
+        self.state.games.insert("Desperados3".into(), new_game);
+        self.select_game("Desperados3".to_string());
+        self.store_state();
+        Ok(())
+    }
+
+    /// Shows the messages from the metadata that apply to this version and weren't shown before.
+    pub fn show_messages(&mut self) {
+        if let Some(meta) = &self.metadata {
+            for msg in &meta.messages {
+                if msg.version.matches(&APP_VERSION)
+                    && (self.state.shown_messages.insert(msg.id.clone()) || msg.show_always)
+                {
+                    dialog::message(&msg.id, &msg.message, msg.icon);
+                }
+            }
+        }
+    }
+
+    /// Selects the previously selected or any set up game and checks its mod loader and mods.
+    pub fn ensure_game_selected(&mut self) {
+        let selected_game = self
+            .state
+            .selected_game
+            .as_ref()
+            .and_then(|game_id| self.state.games.get(game_id));
+
+        if let Some(selected_game) = selected_game {
+            let id = selected_game.game.id.clone();
+            self.select_game(id);
+        } else if let Some(game_id) = self.state.games.keys().next().cloned() {
+            self.select_game(game_id);
+        }
+    }
+
+    fn select_game(&mut self, id: String) {
+        if let Some(game) = self.state.games.get_mut(&id) {
+            self.state.selected_game = Some(id);
+            game.update_modloader_status(&self.env);
+            if let Some(meta) = &self.metadata {
+                game.update_mods_meta(&meta.mods);
+            }
+            self.fetch_game_metadata();
+            self.offer_adoption();
+        } else {
+            self.state.selected_game = None;
+        }
+    }
+
+    fn fetch_game_metadata(&mut self) {
+        log::info!("Fetching game metadata");
+        let mut fetch = || {
+            let game = self.state.current_game_mut()?;
+            let meta = self.metadata.as_mut()?;
+            if !meta.game_mods.contains_key(&game.game.id) {
+                let game_mods = game.game.fetch_mods(&*self.env.fetcher)?;
+                for warning in game_mods.mods.warnings("mod") {
+                    let warning = format!("{} ({}): {}", game.game.name, game.game.id, warning);
+                    log::warn!("{}", warning);
+                    meta.warnings.push(warning);
+                }
+                let game_mods: HashMap<String, Mod> = game_mods.into();
+                game.update_mods_meta(&game_mods);
+                meta.game_mods.insert(game.game.id.clone(), game_mods);
+                self.unmanaged = None;
+            }
+            Some(())
+        };
+        let _ = fetch();
+    }
+
+    pub fn store_state(&mut self) {
+        // Anything shown in the UI may have changed
+        self.running_game_ids = None;
+        self.unmanaged = None;
+        if let Err(error) = self.data_file.store(&self.state) {
+            crate::show_error(&format!("Failed to save data.json: {:#}", error));
+        }
+    }
+}
+
+impl Drop for Manager {
+    fn drop(&mut self) {
+        self.reap_games();
+        for running in self.running_games.drain(..) {
+            if let Some(game) = self.state.games.get_mut(&running.game_id) {
+                game.record_launch(running.abandon());
+            }
+        }
+        self.store_state();
+        self.config.store();
+    }
+}
+
+fn load_state() -> anyhow::Result<(JsonFile, State)> {
+    let data_path = crate::utils::app_dir(AppDataType::UserData)
+        .context("Failed to get data directory")?
+        .join("data.json");
+    log::info!("Using data file at: '{}'", data_path.to_string_lossy());
+    let data_file = JsonFile::new(data_path, DATA_BACKUPS, STATE_MIGRATIONS);
+
+    let state = match data_file.load() {
+        Loaded::Ok(state) => state,
+        Loaded::Missing => {
+            log::info!("No data.json found");
+            State::default()
+        }
+        Loaded::Recovered {
+            value,
+            backup,
+            error,
+        } => {
+            dialog::message(
+                "Restored backup",
+                &format!(
+                    "data.json could not be loaded:\n\n{:#}\n\n\
+                     ytinu restored the backup '{}' instead. Changes made after it was created are \
+                     lost. The damaged file was kept as '{}'.",
+                    error,
+                    backup.display(),
+                    data_file.broken_path().display()
+                ),
+                MessageIcon::Warning,
+            );
+            value
+        }
+        Loaded::Unusable(error) => return Err(error.context("data.json can't be used")),
+        Loaded::Failed(error) => {
+            crate::show_error(&format!(
+                "data.json and its backups could not be loaded:\n\n{:#}\n\n\
+                 ytinu starts without any set up games. The damaged file was kept as '{}'.",
+                error,
+                data_file.broken_path().display()
+            ));
+            State::default()
+        }
+    };
+
+    Ok((data_file, state))
+}
+
+fn fetch_metadata(fetcher: &dyn Fetcher) -> Option<Metadata> {
+    crate::signature::fetch_signed_json::<MetadataIn>(fetcher, METADATA_URL, "metadata").map(
+        |meta| {
+            for warning in meta.warnings() {
+                log::warn!("{}", warning);
+            }
+            meta.into()
+        },
+    )
+}
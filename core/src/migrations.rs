@@ -0,0 +1,20 @@
+//! Upgrades of `data.json` and `config.json` from older formats.
+//!
+//! Before changing how [`State`](crate::data::State) or [`Config`](crate::data::Config) is
+//! serialized, append a migration that converts the JSON of the current format to the new one.
+//! Migrations work on the raw JSON so they keep working after the types change again.
+
+use serde_json::Value;
+
+use crate::persist::Migration;
+
+/// Migrations of `data.json`
+pub static STATE_MIGRATIONS: &[Migration] = &[unversioned];
+
+/// Migrations of `config.json`
+pub static CONFIG_MIGRATIONS: &[Migration] = &[unversioned];
+
+/// Format 0 to 1: Files were stored without the format. The data itself is unchanged.
+fn unversioned(data: Value) -> anyhow::Result<Value> {
+    Ok(data)
+}
@@ -0,0 +1,209 @@
+//! `ytinu-cli pack`: builds a release zip from a mod directory and creates the matching metadata entry.
+
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context};
+use zip::{write::FileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use crate::{data::Mod, fs::RealFs};
+
+#[derive(Debug, Clone, Default)]
+pub struct PackOptions {
+    /// Mod directory or a single plugin DLL
+    pub path: PathBuf,
+    /// Detected from the plugin if not set
+    pub id: Option<String>,
+    /// Detected from the plugin if not set
+    pub version: Option<semver::Version>,
+    /// Directory to write the release zip to
+    pub out: Option<PathBuf>,
+    /// Download URL of the release zip for the metadata entry. Required.
+    pub url: Option<String>,
+    /// The directory contains the game's directory structure, e.g. `BepInEx/plugins/...`
+    pub extract_to_root: bool,
+    pub dev_mod: bool,
+}
+
+pub struct Package {
+    pub zip: PathBuf,
+    pub entry: Mod,
+}
+
+impl Package {
+    /// Metadata entry without unset and local-only fields
+    pub fn entry_json(&self) -> serde_json::Result<String> {
+        let entry = match serde_json::to_value(&self.entry)? {
+            serde_json::Value::Object(entry) => entry
+                .into_iter()
+                .filter(|(key, value)| !value.is_null() && key != "local")
+                .collect(),
+            entry => entry,
+        };
+        serde_json::to_string_pretty(&entry)
+    }
+}
+
+pub fn pack(options: &PackOptions) -> anyhow::Result<Package> {
+    // The entry is only usable in the metadata with the URL the zip is published at
+    let url = options
+        .url
+        .clone()
+        .context("Use --url to set the download URL the release zip will be published at")?;
+    match reqwest::Url::parse(&url) {
+        Ok(parsed) => ensure!(
+            parsed.scheme() == "https" || parsed.scheme() == "http",
+            "Unsupported URL scheme '{}' in '{}'",
+            parsed.scheme(),
+            url
+        ),
+        Err(error) => bail!("Invalid URL '{}': {}", url, error),
+    }
+
+    let path = options
+        .path
+        .canonicalize()
+        .with_context(|| format!("Not found: '{}'", options.path.to_string_lossy()))?;
+    let (root, files) = if path.is_dir() {
+        (path.clone(), list_files(&path)?)
+    } else {
+        let root = path.parent().context("Invalid path")?.to_path_buf();
+        (root, vec![PathBuf::from(path.file_name().unwrap())])
+    };
+    ensure!(
+        !files.is_empty(),
+        "No files to pack in '{}'",
+        path.to_string_lossy()
+    );
+
+    let (assembly, plugin) = files
+        .iter()
+        .filter(|file| file.extension().is_some_and(|ext| ext == "dll"))
+        .filter_map(|file| crate::dotnet::read_assembly(&RealFs, &root.join(file)).ok())
+        .find_map(|assembly| {
+            let plugin = assembly.plugins.first()?.clone();
+            Some((assembly, plugin))
+        })
+        .map_or((None, None), |(assembly, plugin)| {
+            (Some(assembly), Some(plugin))
+        });
+
+    let id = match (&options.id, &assembly) {
+        (Some(id), _) => id.clone(),
+        (None, Some(assembly)) => assembly
+            .name
+            .clone()
+            .context("Plugin assembly has no name, use --id to set the mod id")?,
+        (None, None) => bail!("No BepInEx plugin found, use --id to set the mod id"),
+    };
+    crate::local_mod::validate_mod_id(&id)?;
+    let version = match (&options.version, &plugin) {
+        (Some(version), _) => version.clone(),
+        (None, Some(plugin)) => crate::plugins::parse_version(&plugin.version)
+            .with_context(|| format!("Invalid plugin version '{}'", plugin.version))?,
+        (None, None) => bail!("No BepInEx plugin found, use --version to set the version"),
+    };
+
+    let zip_name = format!("{}-{}.zip", id, version);
+    let out = match &options.out {
+        Some(out) => out.clone(),
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+    std::fs::create_dir_all(&out)
+        .with_context(|| format!("Failed to create '{}'", out.to_string_lossy()))?;
+    let zip = out
+        .canonicalize()
+        .with_context(|| format!("Invalid path: '{}'", out.to_string_lossy()))?
+        .join(zip_name);
+    ensure!(
+        !zip.starts_with(&root) || !path.is_dir(),
+        "The output directory must not be inside the mod directory"
+    );
+    write_zip(&root, &files, &zip)?;
+
+    let installed_files = if options.extract_to_root {
+        files.iter().map(|file| to_slash(file)).collect()
+    } else {
+        vec![format!("BepInEx/plugins/{}", id)]
+    };
+    let size = std::fs::metadata(&zip)
+        .with_context(|| format!("Failed to read '{}'", zip.to_string_lossy()))?
+        .len();
+
+    let entry = Mod {
+        name: plugin.map(|p| p.name).unwrap_or_else(|| id.clone()),
+        download: url,
+        id,
+        version,
+        extract_to_root: options.extract_to_root,
+        files: Some(installed_files),
+        hash: Some(crate::utils::checksum(&RealFs, &zip)?),
+        size: Some(size),
+        local: false,
+        dev_mod: options.dev_mod,
+        source: None,
+        homepage: None,
+        description: None,
+        ytinu_version: None,
+    };
+    Ok(Package { zip, entry })
+}
+
+/// All files in a directory relative to it, sorted to create reproducible zips.
+fn list_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        let entries = dir
+            .join(&relative)
+            .read_dir()
+            .with_context(|| format!("Failed to list files in '{}'", relative.to_string_lossy()))?;
+        for entry in entries {
+            let entry = entry.context("Failed to list files")?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn write_zip(root: &Path, files: &[PathBuf], target: &Path) -> anyhow::Result<()> {
+    log::info!(
+        "Packing {} files from '{}' into '{}'",
+        files.len(),
+        root.to_string_lossy(),
+        target.to_string_lossy()
+    );
+    let file = File::create(target)
+        .with_context(|| format!("Failed to create '{}'", target.to_string_lossy()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(DateTime::default());
+    for file in files {
+        let mut bytes = Vec::new();
+        File::open(root.join(file))
+            .and_then(|mut f| f.read_to_end(&mut bytes))
+            .with_context(|| format!("Failed to read '{}'", file.to_string_lossy()))?;
+        zip.start_file(to_slash(file), options)?;
+        zip.write_all(&bytes)?;
+    }
+    zip.finish()
+        .with_context(|| format!("Failed to write '{}'", target.to_string_lossy()))?;
+    Ok(())
+}
+
+fn to_slash(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,306 @@
+//! Crash-safe storage of JSON files like `data.json`.
+//!
+//! Files are written to a temporary file, flushed to disk and then renamed over the old file, so
+//! a crash leaves either the old or the new version behind. Before a changed file is replaced, the
+//! previous version is kept as `<name>.bkp.1`, shifting older backups up to `<name>.bkp.<n>`. If
+//! the file is damaged, the newest valid backup is used instead. Files that can't be read or that
+//! were written by a newer version of ytinu aren't damaged and are left alone.
+//!
+//! The value is stored as `{"format": <format>, "data": <value>}`. Files in an older format are
+//! upgraded by a chain of [`Migration`]s when loading, keeping a copy of the file as
+//! `<name>.format<n>`. Files written before the format was stored are format 0.
+
+use std::{
+    fs::File,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+/// Converts the data of format `n` to format `n + 1`
+pub type Migration = fn(Value) -> anyhow::Result<Value>;
+
+/// A versioned JSON file with rotating backups
+pub struct JsonFile {
+    path: PathBuf,
+    backups: usize,
+    /// Migration `n` upgrades format `n`. The current format is the number of migrations.
+    migrations: &'static [Migration],
+}
+
+/// Result of [`JsonFile::load`]
+pub enum Loaded<T> {
+    /// Neither the file nor any backup exists
+    Missing,
+    Ok(T),
+    /// The file couldn't be loaded but a backup could
+    Recovered {
+        value: T,
+        backup: PathBuf,
+        error: anyhow::Error,
+    },
+    /// The file isn't damaged but can't be used, e.g. because it was written by a newer version
+    /// of ytinu or reading it failed. It must not be overwritten.
+    Unusable(anyhow::Error),
+    /// Neither the file nor any of its backups could be loaded
+    Failed(anyhow::Error),
+}
+
+/// Why a file couldn't be read
+enum ReadError {
+    Missing(anyhow::Error),
+    Unusable(anyhow::Error),
+    /// The file can't be parsed or upgraded
+    Damaged(anyhow::Error),
+}
+
+impl ReadError {
+    fn into_inner(self) -> anyhow::Error {
+        match self {
+            ReadError::Missing(error) | ReadError::Unusable(error) | ReadError::Damaged(error) => {
+                error
+            }
+        }
+    }
+}
+
+impl JsonFile {
+    pub fn new(path: PathBuf, backups: usize, migrations: &'static [Migration]) -> Self {
+        JsonFile {
+            path,
+            backups,
+            migrations,
+        }
+    }
+
+    /// Format that is written by [`JsonFile::store`]
+    pub fn format(&self) -> usize {
+        self.migrations.len()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where a file that failed to load is kept instead of overwriting it
+    pub fn broken_path(&self) -> PathBuf {
+        with_suffix(&self.path, ".broken")
+    }
+
+    /// Where a copy of the file is kept before upgrading it from `format`. Backups are copied next
+    /// to themselves.
+    pub fn pre_migration_path(&self, format: usize) -> PathBuf {
+        with_suffix(&self.path, &format!(".format{}", format))
+    }
+
+    /// Path of a backup. Generation 1 is the newest.
+    pub fn backup_path(&self, generation: usize) -> PathBuf {
+        with_suffix(&self.path, &format!(".bkp.{}", generation))
+    }
+
+    /// Existing backups, newest first. Includes the single backup of older ytinu versions.
+    pub fn backups(&self) -> Vec<PathBuf> {
+        (1..=self.backups)
+            .map(|generation| self.backup_path(generation))
+            .chain(std::iter::once(with_suffix(&self.path, ".bkp")))
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// Loads the file or, if it is damaged or missing, the newest backup that can be loaded. A
+    /// damaged file is moved to [`JsonFile::broken_path`] so it isn't overwritten by the next store.
+    pub fn load<T: DeserializeOwned>(&self) -> Loaded<T> {
+        let error = match self.read(&self.path) {
+            Ok(value) => return Loaded::Ok(value),
+            Err(ReadError::Missing(_)) if self.backups().is_empty() => return Loaded::Missing,
+            Err(ReadError::Missing(error)) => error,
+            Err(ReadError::Unusable(error)) => {
+                log::error!("{:#}", error);
+                return Loaded::Unusable(error);
+            }
+            Err(ReadError::Damaged(error)) => {
+                log::error!("{:#}", error);
+                if let Err(error) = std::fs::rename(&self.path, self.broken_path()) {
+                    log::error!(
+                        "Failed to move '{}' out of the way: {}",
+                        self.path.display(),
+                        error
+                    );
+                }
+                error
+            }
+        };
+
+        for backup in self.backups() {
+            match self.read(&backup) {
+                Ok(value) => {
+                    log::warn!(
+                        "Recovered '{}' from '{}'",
+                        self.path.display(),
+                        backup.display()
+                    );
+                    return Loaded::Recovered {
+                        value,
+                        backup,
+                        error,
+                    };
+                }
+                Err(error) => log::warn!("Skipping backup: {:#}", error.into_inner()),
+            }
+        }
+        Loaded::Failed(error)
+    }
+
+    /// Stores `value` if it differs from the stored file, keeping the old version as a backup.
+    pub fn store<T: Serialize>(&self, value: &T) -> anyhow::Result<()> {
+        let json = serde_json::to_vec(&json!({ "format": self.format(), "data": value }))
+            .with_context(|| format!("Failed to serialize '{}'", self.path.display()))?;
+        if matches!(std::fs::read(&self.path), Ok(old) if old == json) {
+            return Ok(());
+        }
+        if self.backups > 0 && self.path.exists() {
+            if let Err(error) = self.rotate_backups() {
+                log::warn!(
+                    "Failed to backup '{}': {:#}. Storing it anyway.",
+                    self.path.display(),
+                    error
+                );
+            }
+        }
+        write_atomic(&self.path, &json)
+    }
+
+    fn rotate_backups(&self) -> anyhow::Result<()> {
+        for generation in (1..self.backups).rev() {
+            let from = self.backup_path(generation);
+            if from.exists() {
+                let to = self.backup_path(generation + 1);
+                std::fs::rename(&from, &to)
+                    .with_context(|| format!("Failed to rename '{}'", from.display()))?;
+            }
+        }
+        // Copying keeps the file in place in case storing the new version fails
+        let backup = self.backup_path(1);
+        std::fs::copy(&self.path, &backup)
+            .with_context(|| format!("Failed to copy to '{}'", backup.display()))?;
+        Ok(())
+    }
+
+    fn read<T: DeserializeOwned>(&self, path: &Path) -> Result<T, ReadError> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                return Err(ReadError::Missing(anyhow!(
+                    "'{}' doesn't exist",
+                    path.display()
+                )))
+            }
+            Err(error) => {
+                return Err(ReadError::Unusable(
+                    anyhow::Error::new(error)
+                        .context(format!("Failed to read '{}'", path.display())),
+                ))
+            }
+        };
+        let value = serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))
+            .map_err(ReadError::Damaged)?;
+        let value = self.migrate(path, value).map_err(|error| match error {
+            ReadError::Damaged(error) => {
+                ReadError::Damaged(error.context(format!("Failed to upgrade '{}'", path.display())))
+            }
+            error => error,
+        })?;
+        serde_json::from_value(value)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))
+            .map_err(ReadError::Damaged)
+    }
+
+    /// Unpacks the data and upgrades it to the current format
+    fn migrate(&self, path: &Path, value: Value) -> Result<Value, ReadError> {
+        let (format, mut data) = match value {
+            Value::Object(mut object) if object.contains_key("format") => {
+                let format = object["format"]
+                    .as_u64()
+                    .context("Invalid format")
+                    .map_err(ReadError::Damaged)?;
+                let data = object
+                    .remove("data")
+                    .context("Missing data")
+                    .map_err(ReadError::Damaged)?;
+                (format as usize, data)
+            }
+            value => (0, value),
+        };
+        if format > self.format() {
+            return Err(ReadError::Unusable(anyhow!(
+                "'{}' was written by a newer version of ytinu (format {}, supported up to {}). \
+                 Please update ytinu.",
+                path.display(),
+                format,
+                self.format()
+            )));
+        }
+        if format == self.format() {
+            return Ok(data);
+        }
+
+        // Next to the migrated file, which may also be a backup
+        let copy = with_suffix(path, &format!(".format{}", format));
+        log::info!(
+            "Upgrading '{}' from format {} to {}. Keeping the old file as '{}'.",
+            path.display(),
+            format,
+            self.format(),
+            copy.display()
+        );
+        std::fs::copy(path, &copy)
+            .with_context(|| format!("Failed to copy to '{}'", copy.display()))
+            .map_err(ReadError::Unusable)?;
+        for (from, migration) in self.migrations.iter().enumerate().skip(format) {
+            data = migration(data)
+                .with_context(|| format!("Migration from format {}", from))
+                .map_err(ReadError::Damaged)?;
+        }
+        Ok(data)
+    }
+}
+
+/// Replaces the file at `path` with `data` so that it is never left partially written.
+pub fn write_atomic(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    let tmp_path = with_suffix(path, ".tmp");
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    })();
+    if let Err(error) = result {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(error).with_context(|| format!("Failed to write '{}'", path.display()));
+    }
+    sync_parent(path);
+    Ok(())
+}
+
+/// Makes the rename durable. Not possible (and not needed) on Windows.
+#[cfg(unix)]
+fn sync_parent(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Err(error) = File::open(parent).and_then(|dir| dir.sync_all()) {
+            log::warn!("Failed to sync '{}': {}", parent.display(), error);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) {}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
@@ -0,0 +1,385 @@
+//! Parser and writer for the `.cfg` files BepInEx plugins store in `BepInEx/config`.
+//!
+//! Only the values of entries are ever changed. All other lines, including comments and
+//! blank lines, are written back exactly as they were read.
+//!
+//! BepInEx escapes backslashes, quotes and control characters in string values. Entries contain
+//! the unescaped values and updates are escaped again.
+
+use std::path::Path;
+
+use anyhow::{ensure, Context};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::Fs;
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct PluginConfigInfo {
+    pub file: String,
+    pub plugin: Option<String>,
+    pub guid: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct ConfigEntry {
+    pub section: String,
+    pub key: String,
+    pub value: String,
+    pub description: Option<String>,
+    pub setting_type: Option<String>,
+    pub default_value: Option<String>,
+    pub acceptable: Option<AcceptableValues>,
+    /// Whether multiple values from `acceptable` can be combined with `,` (flag enums)
+    pub multiple: bool,
+    #[serde(skip)]
+    line: usize,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum AcceptableValues {
+    List { values: Vec<String> },
+    Range { min: String, max: String },
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct ConfigEntryUpdate {
+    pub section: String,
+    pub key: String,
+    /// New value. `None` resets the entry to its default value.
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginConfig {
+    lines: Vec<String>,
+    entries: Vec<ConfigEntry>,
+    crlf: bool,
+    trailing_newline: bool,
+}
+
+#[derive(Default)]
+struct PendingComments {
+    description: Vec<String>,
+    setting_type: Option<String>,
+    default_value: Option<String>,
+    acceptable: Option<AcceptableValues>,
+    multiple: bool,
+}
+
+impl PluginConfig {
+    pub fn load(fs: &dyn Fs, path: &Path) -> anyhow::Result<Self> {
+        let text = fs
+            .read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(String::from_utf8(data)?))
+            .with_context(|| format!("Failed to read '{}'", path.to_string_lossy()))?;
+        Ok(Self::parse(&text))
+    }
+
+    pub fn store(&self, fs: &dyn Fs, path: &Path) -> anyhow::Result<()> {
+        fs.write(path, self.to_string().as_bytes())
+            .with_context(|| format!("Failed to write '{}'", path.to_string_lossy()))
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let crlf = text.contains("\r\n");
+        let trailing_newline = text.ends_with('\n');
+        let lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+        let mut entries = Vec::new();
+        let mut section = String::new();
+        let mut pending = PendingComments::default();
+
+        for (i, line) in lines.iter().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            } else if let Some(description) = trimmed.strip_prefix("##") {
+                pending.description.push(description.trim().to_string());
+            } else if let Some(comment) = trimmed.strip_prefix('#') {
+                pending.parse_annotation(comment.trim());
+            } else if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                section = trimmed[1..trimmed.len() - 1].trim().to_string();
+                pending = PendingComments::default();
+            } else if let Some((key, value)) = trimmed.split_once('=') {
+                let pending = std::mem::take(&mut pending);
+                let is_string = pending.setting_type.as_deref() == Some("String");
+                let read_value = |value: &str| {
+                    if is_string {
+                        unescape(value)
+                    } else {
+                        value.to_string()
+                    }
+                };
+                entries.push(ConfigEntry {
+                    section: section.clone(),
+                    key: key.trim().to_string(),
+                    value: read_value(value.trim()),
+                    description: if pending.description.is_empty() {
+                        None
+                    } else {
+                        Some(pending.description.join("\n"))
+                    },
+                    default_value: pending.default_value.as_deref().map(read_value),
+                    setting_type: pending.setting_type,
+                    acceptable: pending.acceptable,
+                    multiple: pending.multiple,
+                    line: i,
+                });
+            }
+        }
+
+        Self {
+            lines,
+            entries,
+            crlf,
+            trailing_newline,
+        }
+    }
+
+    pub fn entries(&self) -> &[ConfigEntry] {
+        &self.entries
+    }
+
+    /// Plugin name and GUID from the header BepInEx writes at the top of every config file.
+    pub fn plugin_info(&self) -> (Option<String>, Option<String>) {
+        let mut plugin = None;
+        let mut guid = None;
+        for line in self.lines.iter().take_while(|l| !l.trim().starts_with('[')) {
+            let line = line
+                .trim_start_matches('\u{feff}')
+                .trim()
+                .trim_start_matches('#')
+                .trim();
+            if let Some(name) = line.strip_prefix("Settings file was created by plugin") {
+                plugin = Some(name.trim().to_string());
+            } else if let Some(id) = line.strip_prefix("Plugin GUID:") {
+                guid = Some(id.trim().to_string());
+            }
+        }
+        (plugin, guid)
+    }
+
+    pub fn update(&mut self, update: ConfigEntryUpdate) -> anyhow::Result<()> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| e.section == update.section && e.key == update.key)
+            .with_context(|| {
+                format!("No entry '{}' in section [{}]", update.key, update.section)
+            })?;
+
+        let value = match update.value {
+            Some(value) => value.trim().to_string(),
+            None => entry
+                .default_value
+                .clone()
+                .with_context(|| format!("'{}' has no default value", entry.key))?,
+        };
+        entry.validate(&value)?;
+
+        let text = if entry.is_string() {
+            escape(&value)
+        } else {
+            value.clone()
+        };
+        let line = &mut self.lines[entry.line];
+        let indent_len = line.len() - line.trim_start().len();
+        *line = format!("{}{} = {}", &line[..indent_len], entry.key, text);
+        entry.value = value;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for PluginConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let newline = if self.crlf { "\r\n" } else { "\n" };
+        for (i, line) in self.lines.iter().enumerate() {
+            f.write_str(line)?;
+            if i + 1 < self.lines.len() || self.trailing_newline {
+                f.write_str(newline)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PendingComments {
+    fn parse_annotation(&mut self, comment: &str) {
+        if let Some(setting_type) = comment.strip_prefix("Setting type:") {
+            self.setting_type = Some(setting_type.trim().to_string());
+        } else if let Some(default) = comment.strip_prefix("Default value:") {
+            self.default_value = Some(default.trim().to_string());
+        } else if let Some(range) = comment.strip_prefix("Acceptable value range:") {
+            let range = range.trim().trim_start_matches("From").trim();
+            if let Some((min, max)) = range.split_once(" to ") {
+                self.acceptable = Some(AcceptableValues::Range {
+                    min: min.trim().to_string(),
+                    max: max.trim().to_string(),
+                });
+            }
+        } else if let Some(values) = comment.strip_prefix("Acceptable values:") {
+            self.acceptable = Some(AcceptableValues::List {
+                values: values.split(',').map(|v| v.trim().to_string()).collect(),
+            });
+        } else if comment.starts_with("Multiple values can be set at the same time") {
+            self.multiple = true;
+        }
+    }
+}
+
+impl ConfigEntry {
+    fn is_string(&self) -> bool {
+        self.setting_type.as_deref() == Some("String")
+    }
+
+    fn validate(&self, value: &str) -> anyhow::Result<()> {
+        ensure!(
+            self.is_string() || (!value.contains('\n') && !value.contains('\r')),
+            "Values can not contain line breaks"
+        );
+
+        match &self.acceptable {
+            Some(AcceptableValues::List { values }) => {
+                let mut given = if self.multiple {
+                    value.split(',').map(str::trim).collect()
+                } else {
+                    vec![value]
+                };
+                given.retain(|v| !v.is_empty());
+                for v in given {
+                    ensure!(
+                        values.iter().any(|allowed| allowed == v),
+                        "'{}' is not an acceptable value for '{}'. Acceptable values: {}",
+                        v,
+                        self.key,
+                        values.join(", ")
+                    );
+                }
+            }
+            Some(AcceptableValues::Range { min, max }) => {
+                let parse = |v: &str| -> anyhow::Result<f64> {
+                    v.parse()
+                        .with_context(|| format!("'{}' is not a number", v))
+                };
+                let number = parse(value)?;
+                if let (Ok(min), Ok(max)) = (parse(min), parse(max)) {
+                    ensure!(
+                        number >= min && number <= max,
+                        "'{}' must be between {} and {}",
+                        self.key,
+                        min,
+                        max
+                    );
+                }
+            }
+            None => (),
+        }
+
+        match self.setting_type.as_deref() {
+            Some("Boolean") => {
+                ensure!(
+                    value == "true" || value == "false",
+                    "'{}' must be either 'true' or 'false'",
+                    self.key
+                );
+            }
+            Some("Byte") | Some("SByte") | Some("Int16") | Some("UInt16") | Some("Int32")
+            | Some("UInt32") | Some("Int64") | Some("UInt64") => ensure!(
+                value.parse::<i128>().is_ok(),
+                "'{}' must be a whole number",
+                self.key
+            ),
+            Some("Single") | Some("Double") | Some("Decimal") => ensure!(
+                value.parse::<f64>().is_ok(),
+                "'{}' must be a number",
+                self.key
+            ),
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+/// Escapes a string value like BepInEx does
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\0' => escaped.push_str("\\0"),
+            '\u{7}' => escaped.push_str("\\a"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\u{b}' => escaped.push_str("\\v"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '\r' => escaped.push_str("\\r"),
+            '\'' => escaped.push_str("\\'"),
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Unescapes a string value like BepInEx does. Like BepInEx, paths such as `C:\Games` that were
+/// written by hand without escaping are kept as they are.
+fn unescape(text: &str) -> String {
+    if is_unescaped_path(text) {
+        return text.to_string();
+    }
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('0') => value.push('\0'),
+            Some('a') => value.push('\u{7}'),
+            Some('b') => value.push('\u{8}'),
+            Some('t') => value.push('\t'),
+            Some('n') => value.push('\n'),
+            Some('v') => value.push('\u{b}'),
+            Some('f') => value.push('\u{c}'),
+            Some('r') => value.push('\r'),
+            Some(c @ ('\'' | '"' | '\\')) => value.push(c),
+            Some(c) => {
+                value.push('\\');
+                value.push(c);
+            }
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+/// A drive path with single backslashes, matching `^"?\w:\\(?!\\)(?!.+\\\\)` in BepInEx
+fn is_unescaped_path(text: &str) -> bool {
+    let text = text.strip_prefix('"').unwrap_or(text);
+    let mut chars = text.chars();
+    let drive = chars.next();
+    let rest = chars.as_str();
+    drive.is_some_and(|c| c.is_alphanumeric() || c == '_')
+        && rest.starts_with(":\\")
+        && !rest[2..].starts_with('\\')
+        && !rest[2..].contains("\\\\")
+}
+
+/// Checks that `file` names a config file directly inside the config directory.
+pub fn validate_file_name(file: &str) -> anyhow::Result<()> {
+    ensure!(
+        file.ends_with(".cfg")
+            && !file.contains('/')
+            && !file.contains('\\')
+            && !file.starts_with('.'),
+        "Invalid config file name: '{}'",
+        file
+    );
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use std::{cmp::Ordering, collections::HashMap, path::PathBuf};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    data::{Mod, SetupGame},
+    dotnet::AssemblyInfo,
+    fs::Fs,
+    utils::normalize_name,
+};
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct PluginFile {
+    /// Path relative to the plugins directory
+    pub path: String,
+    pub assembly: Option<AssemblyInfo>,
+    /// Set if the file is not a readable .NET assembly
+    pub error: Option<String>,
+    /// Installed mod the file belongs to
+    pub mod_id: Option<String>,
+    /// Known mod that an unmanaged file most likely belongs to
+    pub matched_mod: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct PluginProblem {
+    pub guid: String,
+    pub file: String,
+    pub message: String,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct PluginReport {
+    pub files: Vec<PluginFile>,
+    pub problems: Vec<PluginProblem>,
+}
+
+/// Reads all plugin DLLs of a game and checks their dependencies.
+pub fn scan(fs: &dyn Fs, game: &SetupGame, known_mods: &HashMap<String, Mod>) -> PluginReport {
+    let mut owners = HashMap::new();
+    for id in game.mods.keys() {
+        for path in game.mod_paths(id) {
+            owners.insert(path, id.clone());
+        }
+    }
+    for (id, link) in &game.linked_mods {
+        for file in &link.deployed {
+            owners.insert(game.install_path().join(file), id.clone());
+        }
+    }
+
+    let mut files = Vec::new();
+    for path in find_dlls(fs, game.plugins_path()) {
+        let relative = path
+            .strip_prefix(game.plugins_path())
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        let (assembly, error) = match crate::dotnet::read_assembly(fs, &path) {
+            Ok(assembly) => (Some(assembly), None),
+            Err(error) => (None, Some(format!("{:#}", error))),
+        };
+        let mod_id = path
+            .ancestors()
+            .find_map(|ancestor| owners.get(ancestor))
+            .cloned();
+        let matched_mod = match (&mod_id, &assembly) {
+            (None, Some(assembly)) => match_known_mod(&path, assembly, known_mods),
+            _ => None,
+        };
+        files.push(PluginFile {
+            path: relative,
+            assembly,
+            error,
+            mod_id,
+            matched_mod,
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let problems = check_dependencies(game, &files);
+    PluginReport { files, problems }
+}
+
+/// All `.dll` files in a directory and its subdirectories
+pub(crate) fn find_dlls(fs: &dyn Fs, dir: PathBuf) -> Vec<PathBuf> {
+    let mut dlls = Vec::new();
+    let mut dirs = vec![dir];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs.read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for path in entries {
+            if fs.is_dir(&path) {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "dll") {
+                dlls.push(path);
+            }
+        }
+    }
+    dlls
+}
+
+pub fn match_known_mod(
+    path: &std::path::Path,
+    assembly: &AssemblyInfo,
+    known_mods: &HashMap<String, Mod>,
+) -> Option<String> {
+    let mut names = Vec::new();
+    if let Some(stem) = path.file_stem() {
+        names.push(normalize_name(&stem.to_string_lossy()));
+    }
+    if let Some(name) = &assembly.name {
+        names.push(normalize_name(name));
+    }
+    for plugin in &assembly.plugins {
+        names.push(normalize_name(&plugin.name));
+        names.push(normalize_name(&plugin.guid));
+        if let Some(last) = plugin.guid.rsplit('.').next() {
+            names.push(normalize_name(last));
+        }
+    }
+    names.retain(|name| !name.is_empty());
+
+    known_mods
+        .values()
+        .find(|m| {
+            let id = normalize_name(&m.id);
+            let name = normalize_name(&m.name);
+            names.iter().any(|n| *n == id || *n == name)
+        })
+        .map(|m| m.id.clone())
+}
+
+fn check_dependencies(game: &SetupGame, files: &[PluginFile]) -> Vec<PluginProblem> {
+    let plugins: Vec<_> = files
+        .iter()
+        .filter_map(|file| Some((file, file.assembly.as_ref()?)))
+        .flat_map(|(file, assembly)| assembly.plugins.iter().map(move |p| (file, p)))
+        .collect();
+    let available: HashMap<&str, &str> = plugins
+        .iter()
+        .map(|(_, p)| (p.guid.as_str(), p.version.as_str()))
+        .collect();
+    let process = crate::launch::find_executable(game).ok().and_then(|exe| {
+        exe.file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+    });
+
+    let mut problems = Vec::new();
+    for (file, plugin) in &plugins {
+        let mut problem = |message: String| {
+            problems.push(PluginProblem {
+                guid: plugin.guid.clone(),
+                file: file.path.clone(),
+                message,
+            })
+        };
+
+        for dependency in &plugin.dependencies {
+            match available.get(dependency.guid.as_str()) {
+                None if !dependency.soft => problem(format!(
+                    "'{}' requires '{}' which is not installed",
+                    plugin.name, dependency.guid
+                )),
+                Some(version) => {
+                    if let Some(min_version) = &dependency.min_version {
+                        if compare_versions(version, min_version) == Ordering::Less {
+                            problem(format!(
+                                "'{}' requires '{}' version {} or newer but version {} is installed",
+                                plugin.name, dependency.guid, min_version, version
+                            ));
+                        }
+                    }
+                }
+                None => (),
+            }
+        }
+
+        if let Some(process) = &process {
+            let matches = plugin.processes.iter().any(|p| {
+                let p = p.to_lowercase();
+                *process == p || process.split('.').next() == p.split('.').next()
+            });
+            if !plugin.processes.is_empty() && !matches {
+                problem(format!(
+                    "'{}' is only loaded for {} and will be skipped for this game",
+                    plugin.name,
+                    plugin.processes.join(", ")
+                ));
+            }
+        }
+    }
+    problems
+}
+
+/// Compares `System.Version` style version strings like `1.2.3.4` by their numeric components.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split('.')
+            .map(|part| {
+                let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect()
+    };
+    let (a, b) = (parse(a), parse(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Parses `System.Version` style versions like `1.2.3.4` into a semver version by ignoring the
+/// revision component.
+pub fn parse_version(version: &str) -> Option<semver::Version> {
+    let mut parts = version.split('.').map(|p| p.parse::<u64>());
+    Some(semver::Version::new(
+        parts.next()?.ok()?,
+        parts.next().unwrap_or(Ok(0)).ok()?,
+        parts.next().unwrap_or(Ok(0)).ok()?,
+    ))
+}
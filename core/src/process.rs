@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct GameProcess {
+    pub pid: u32,
+    pub exe: PathBuf,
+}
+
+/// Finds running processes whose executable or loaded libraries live inside `dir`.
+pub fn find_processes_in(dir: &Path) -> Vec<GameProcess> {
+    let dir = match dir.canonicalize() {
+        Ok(dir) => dir,
+        Err(_) => return Vec::new(),
+    };
+    match list_processes(&dir) {
+        Ok(processes) => processes,
+        Err(error) => {
+            log::warn!("Failed to list running processes: {:#}", error);
+            Vec::new()
+        }
+    }
+}
+
+pub fn is_running_in(dir: &Path) -> bool {
+    !find_processes_in(dir).is_empty()
+}
+
+#[cfg(target_os = "linux")]
+fn list_processes(dir: &Path) -> anyhow::Result<Vec<GameProcess>> {
+    use anyhow::Context;
+
+    let own_pid = std::process::id();
+    let mut processes = Vec::new();
+    for entry in std::fs::read_dir("/proc").context("Failed to read /proc")? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let pid = match entry.file_name().to_string_lossy().parse::<u32>() {
+            Ok(pid) if pid != own_pid => pid,
+            _ => continue,
+        };
+        // Processes of other users or ones that exited in the meantime can't be inspected
+        let exe = match std::fs::read_link(entry.path().join("exe")) {
+            Ok(exe) => exe,
+            Err(_) => continue,
+        };
+        if exe.starts_with(dir) {
+            processes.push(GameProcess { pid, exe });
+            continue;
+        }
+        // Games running through Wine/Proton have the Wine loader as executable,
+        // so also check for files from the game directory mapped into memory.
+        if let Ok(maps) = std::fs::read_to_string(entry.path().join("maps")) {
+            let mapped = maps
+                .lines()
+                .filter_map(|line| line.split_whitespace().nth(5))
+                .find(|file| Path::new(file).starts_with(dir));
+            if let Some(file) = mapped {
+                processes.push(GameProcess {
+                    pid,
+                    exe: PathBuf::from(file),
+                });
+            }
+        }
+    }
+    Ok(processes)
+}
+
+#[cfg(windows)]
+fn list_processes(dir: &Path) -> anyhow::Result<Vec<GameProcess>> {
+    use anyhow::Context;
+
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-Process | Where-Object Path | ForEach-Object { \"$($_.Id)|$($_.Path)\" }",
+        ])
+        .output()
+        .context("Failed to run powershell")?;
+    Ok(parse_pid_path_lines(&output.stdout, '|', dir))
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn list_processes(dir: &Path) -> anyhow::Result<Vec<GameProcess>> {
+    use anyhow::Context;
+
+    let output = std::process::Command::new("ps")
+        .args(["-axo", "pid=,comm="])
+        .output()
+        .context("Failed to run ps")?;
+    Ok(parse_pid_path_lines(&output.stdout, ' ', dir))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn parse_pid_path_lines(output: &[u8], separator: char, dir: &Path) -> Vec<GameProcess> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .filter_map(|line| {
+            let (pid, exe) = line.trim().split_once(separator)?;
+            let exe = Path::new(exe.trim()).canonicalize().ok()?;
+            Some(GameProcess {
+                pid: pid.trim().parse().ok()?,
+                exe,
+            })
+        })
+        .filter(|process| process.exe.starts_with(dir))
+        .collect()
+}
@@ -0,0 +1,89 @@
+//! ed25519 signatures of the metadata files and ytinu releases.
+//!
+//! Every signed file `<url>` has a detached signature at `<url>.sig` containing the hex encoded
+//! signature of the file's exact bytes. Signatures are created with `ytinu-cli sign`.
+
+use anyhow::{bail, Context};
+use ed25519_compact::{KeyPair, PublicKey, Seed, Signature};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    fetch::Fetcher,
+    utils::{from_hex, to_hex},
+};
+
+/// Public key of the ytinu maintainers that metadata and releases must be signed with
+pub static PUBLIC_KEY: &str = "6e13b01608af6904aef3e210afc9d5cda8aeefbde05c6e6a198a2472e98895ae";
+
+/// Debug builds accept a different key from this environment variable, e.g. for testing
+/// against a local metadata server.
+#[cfg(debug_assertions)]
+static PUBLIC_KEY_ENV: &str = "YTINU_PUBLIC_KEY";
+
+pub fn signature_url(url: &str) -> String {
+    format!("{}.sig", url)
+}
+
+fn public_key() -> anyhow::Result<PublicKey> {
+    #[cfg(debug_assertions)]
+    let key = std::env::var(PUBLIC_KEY_ENV).unwrap_or_else(|_| PUBLIC_KEY.to_string());
+    #[cfg(not(debug_assertions))]
+    let key = PUBLIC_KEY.to_string();
+    from_hex(&key)
+        .and_then(|key| PublicKey::from_slice(&key).ok())
+        .context("Invalid public key")
+}
+
+/// Verifies `data` against a hex encoded signature.
+pub fn verify(data: &[u8], signature: &str) -> anyhow::Result<()> {
+    let signature = from_hex(signature.trim())
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+        .context("Malformed signature")?;
+    if let Err(error) = public_key()?.verify(data, &signature) {
+        bail!("Invalid signature: {}", error);
+    }
+    Ok(())
+}
+
+/// Downloads the signature of a file downloaded from `url` and verifies it.
+pub fn verify_download(fetcher: &dyn Fetcher, data: &[u8], url: &str) -> anyhow::Result<()> {
+    let signature = fetcher
+        .fetch(&signature_url(url))
+        .with_context(|| format!("No signature found for '{}'", url))?;
+    let signature = String::from_utf8(signature).context("Malformed signature")?;
+    verify(data, &signature).with_context(|| format!("Failed to verify '{}'", url))
+}
+
+/// Downloads and parses a signed JSON file. Errors are logged and rejected signatures are also
+/// shown to the user.
+pub fn fetch_signed_json<T: DeserializeOwned>(
+    fetcher: &dyn Fetcher,
+    url: &str,
+    name: &str,
+) -> Option<T> {
+    let data = fetcher
+        .fetch(url)
+        .map_err(|e| log::error!("Failed to get {}: {:#}", name, e))
+        .ok()?;
+    if let Err(error) = verify_download(fetcher, &data, url) {
+        crate::show_error(&format!(
+            "The {} was rejected because its signature could not be verified:\n\n{:#}",
+            name, error
+        ));
+        return None;
+    }
+    serde_json::from_slice(&data)
+        .map_err(|e| log::error!("Failed to parse {}: {}", name, e))
+        .ok()
+}
+
+/// Signs `data` with the hex encoded 32 byte secret seed. Returns the hex encoded signature and
+/// public key.
+pub fn sign(data: &[u8], seed: &str) -> anyhow::Result<(String, String)> {
+    let seed = from_hex(seed.trim())
+        .and_then(|seed| Seed::from_slice(&seed).ok())
+        .context("The key must be a hex encoded 32 byte seed")?;
+    let key_pair = KeyPair::from_seed(seed);
+    let signature = key_pair.sk.sign(data, None);
+    Ok((to_hex(&signature[..]), to_hex(&key_pair.pk[..])))
+}
@@ -0,0 +1,236 @@
+use std::{
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context};
+use app_dirs::AppDataType;
+
+use crate::{data::MessageIcon, dialog, fs::Fs, show_error, Env};
+
+pub trait ErrorExt {
+    fn unwrap_or_msg(self, title: &'static str);
+}
+
+impl<T, E: std::fmt::Display> ErrorExt for Result<T, E> {
+    fn unwrap_or_msg(self, title: &'static str) {
+        if let Err(error) = self {
+            log::error!("{}: {}", title, error);
+            dialog::message(
+                title,
+                &format!("{}:\n\n{}", title, error),
+                MessageIcon::Error,
+            );
+        }
+    }
+}
+
+pub fn app_dir(dir_type: AppDataType) -> Result<PathBuf, app_dirs::AppDirsError> {
+    app_dirs::data_root(dir_type).and_then(|path| {
+        let path = path.join("ytinu");
+        std::fs::create_dir_all(&path)?;
+        Ok(path)
+    })
+}
+
+pub fn checksum(fs: &dyn Fs, path: &Path) -> Result<String, std::io::Error> {
+    Ok(hash(&fs.read(path)?))
+}
+
+/// Hex encoded blake2s hash as used for the checksums in the metadata
+pub fn hash(bytes: &[u8]) -> String {
+    blake2s_simd::blake2s(bytes).to_hex().to_string()
+}
+
+/// Lowercase alphanumeric version of a name to compare mod, plugin and file names
+pub fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+pub fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn open_url(url: &str) -> anyhow::Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .spawn()
+        .with_context(|| format!("Failed to open '{}'", url))?;
+    Ok(())
+}
+
+pub fn open_dir(path: &Path) {
+    if !path.exists() {
+        show_error(&format!(
+            "'{}' doesn't exist. You might have to start the game once to generate it.",
+            path.to_string_lossy()
+        ));
+        return;
+    }
+
+    let windir = std::env::var_os("WINDIR");
+    let windir = match windir.as_ref() {
+        Some(val) => Path::new(val),
+        None => {
+            show_error("Couldn't find explorer: WINDIR is not defined");
+            return;
+        }
+    };
+
+    if let Err(error) = Command::new(windir.join("explorer.exe")).arg(path).spawn() {
+        show_error(&format!("Failed to open directory: {}", error));
+    }
+}
+
+pub fn download_cached(
+    env: &Env,
+    url: &str,
+    name: &str,
+    target: &Path,
+) -> anyhow::Result<Option<PathBuf>> {
+    if let Ok(path) = app_dirs::data_root(app_dirs::AppDataType::UserCache) {
+        let path = path.join("ytinu").join("cache").join(name);
+        if env.fs.exists(&path) {
+            log::info!("Found '{}' in cache", url);
+        } else {
+            download(env, url, &path)?;
+        }
+        Ok(Some(path))
+    } else {
+        download(env, url, target)?;
+        Ok(None)
+    }
+}
+
+pub fn create_parent_dirs(fs: &dyn Fs, path: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs.create_dir_all(parent).with_context(|| {
+            format!("Failed to create directory '{}'", parent.to_string_lossy())
+        })?;
+    } else {
+        bail!(
+            "Failed to get parent directory of: '{}'",
+            path.to_string_lossy(),
+        );
+    }
+    Ok(())
+}
+
+pub fn download(env: &Env, url: &str, path: &Path) -> anyhow::Result<()> {
+    log::info!("Downloading '{}' to '{}'", url, path.to_string_lossy());
+
+    let data = env.fetcher.fetch(url)?;
+    create_parent_dirs(&*env.fs, path)?;
+    env.fs
+        .write(path, &data)
+        .with_context(|| format!("Failed to write to '{}'", path.to_string_lossy()))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+pub fn unzip(fs: &dyn Fs, from: &Path, to: &Path) -> anyhow::Result<()> {
+    log::info!(
+        "Unzipping '{}' to '{}'",
+        from.to_string_lossy(),
+        to.to_string_lossy()
+    );
+
+    create_parent_dirs(fs, to)?;
+
+    let data = fs
+        .read(from)
+        .with_context(|| format!("Failed to read '{}'", from.to_string_lossy()))?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .with_context(|| format!("Failed to extract '{}'", from.to_string_lossy()))?;
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .with_context(|| format!("Failed to extract '{}'", from.to_string_lossy()))?;
+        let path = match file.enclosed_name() {
+            Some(name) => to.join(name),
+            None => bail!(
+                "Failed to extract '{}': Invalid file path '{}'",
+                from.to_string_lossy(),
+                file.name()
+            ),
+        };
+        let context = || format!("Failed to extract '{}'", path.to_string_lossy());
+        if file.is_dir() {
+            fs.create_dir_all(&path).with_context(context)?;
+            continue;
+        }
+        create_parent_dirs(fs, &path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).with_context(context)?;
+        fs.write(&path, &data).with_context(context)?;
+        if let Some(mode) = file.unix_mode() {
+            fs.set_unix_mode(&path, mode).with_context(context)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn download_cached_and_unzip(
+    env: &Env,
+    url: &str,
+    name: &str,
+    zip_target: &Path,
+    unzip_target: &Path,
+) -> anyhow::Result<()> {
+    let (is_cached, path) = match crate::utils::download_cached(env, url, name, zip_target)
+        .context("Failed to download")?
+    {
+        Some(path) => (true, path),
+        None => (false, zip_target.to_path_buf()),
+    };
+    crate::utils::unzip(&*env.fs, &path, unzip_target).context("Failed to extract")?;
+    if !is_cached {
+        if let Err(error) = env.fs.remove_file(&path) {
+            log::error!(
+                "Failed to cleanup '{}' after extraction: {}",
+                path.to_string_lossy(),
+                error
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn remove_file_or_dir(fs: &dyn Fs, path: impl AsRef<Path>) -> Result<(), std::io::Error> {
+    let path = path.as_ref();
+    if fs.is_file(path) {
+        fs.remove_file(path)
+    } else if fs.is_dir(path) {
+        fs.remove_dir_all(path)
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+//! Adopting manually installed mods from the plugins directory.
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::{mod_entry, Fixture, INSTALL_PATH};
+use ytinu_core::adopt::{adopt, find_unmanaged};
+
+static PLUGIN: &[u8] = include_bytes!("fixtures/FooPlugin.dll");
+
+fn known_mods() -> HashMap<String, ytinu_core::data::Mod> {
+    HashMap::from([("Foo".to_string(), mod_entry("Foo", "1.0.0", "Foo.zip"))])
+}
+
+fn add_plugin_file(fixture: &Fixture, path: &str, data: &[u8]) {
+    fixture.fs.add_file(
+        format!("{}/BepInEx/plugins/{}", INSTALL_PATH, path),
+        data.to_vec(),
+    );
+}
+
+fn adopt_foo(fixture: &mut Fixture) -> Vec<String> {
+    let known_mods = known_mods();
+    let unmanaged = find_unmanaged(&*fixture.env.fs, &fixture.game, &known_mods);
+    let m = known_mods["Foo"].clone();
+    assert!(adopt(&*fixture.env.fs, &mut fixture.game, m, &unmanaged));
+    fixture.game.mods["Foo"].files.clone().unwrap()
+}
+
+#[test]
+fn adopt_plugin_directory() {
+    let mut fixture = Fixture::new();
+    add_plugin_file(&fixture, "Foo/Foo.dll", PLUGIN);
+    add_plugin_file(&fixture, "Foo/assets/icon.png", b"icon");
+
+    assert_eq!(adopt_foo(&mut fixture), ["BepInEx/plugins/Foo"]);
+    // The version is read from the plugin
+    assert_eq!(fixture.game.mods["Foo"].version.to_string(), "1.2.3");
+}
+
+#[test]
+fn adopt_only_matched_files_of_shared_directory() {
+    let mut fixture = Fixture::new();
+    add_plugin_file(&fixture, "Shared/Foo.dll", PLUGIN);
+    add_plugin_file(&fixture, "Shared/Other.dll", b"other");
+
+    assert_eq!(adopt_foo(&mut fixture), ["BepInEx/plugins/Shared/Foo.dll"]);
+    let unmanaged = find_unmanaged(&*fixture.env.fs, &fixture.game, &known_mods());
+    let paths: Vec<_> = unmanaged.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, ["Shared/Other.dll"]);
+
+    fixture.game.remove_mod(&fixture.env, "Foo").unwrap();
+    assert!(!fixture.exists("BepInEx/plugins/Shared/Foo.dll"));
+    assert!(fixture.exists("BepInEx/plugins/Shared/Other.dll"));
+}
+
+#[test]
+fn exact_match_of_single_dll_mod() {
+    let mut fixture = Fixture::new();
+    add_plugin_file(&fixture, "renamed.dll", PLUGIN);
+    let mut m = mod_entry("Bar", "2.0.0", "Bar.dll");
+    m.hash = Some(ytinu_core::utils::hash(PLUGIN));
+    let known_mods = HashMap::from([("Bar".to_string(), m)]);
+
+    let unmanaged = find_unmanaged(&*fixture.env.fs, &fixture.game, &known_mods);
+    assert_eq!(unmanaged.len(), 1);
+    assert!(unmanaged[0].exact);
+    assert_eq!(unmanaged[0].matched_mod.as_deref(), Some("Bar"));
+    assert_eq!(unmanaged[0].version.as_deref(), Some("2.0.0"));
+    let m = known_mods["Bar"].clone();
+    assert!(adopt(&*fixture.env.fs, &mut fixture.game, m, &unmanaged));
+    assert_eq!(
+        fixture.game.mods["Bar"].files.as_deref().unwrap(),
+        ["BepInEx/plugins/renamed.dll"]
+    );
+}
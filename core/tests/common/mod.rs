@@ -0,0 +1,115 @@
+//! Fixtures for hermetic tests: a fetcher that redirects all downloads to a local file server and
+//! an in-memory game directory.
+
+// Each test crate only uses some of the fixtures
+#![allow(dead_code)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{Arc, Once},
+};
+
+use serde_json::json;
+use ytinu_core::{
+    data::{Game, MessageIcon, Mod, SetupGame},
+    dialog::Dialogs,
+    fetch::{Fetcher, HttpFetcher},
+    fs::{Fs, MemoryFs},
+    Env,
+};
+use ytinu_test_utils::FileServer;
+
+pub static INSTALL_PATH: &str = "/games/Desperados III";
+
+/// Downloads `https://<host>/<path>` from `/<host>/<path>` of the file server
+pub struct LocalFetcher {
+    url: String,
+}
+
+impl Fetcher for LocalFetcher {
+    fn fetch(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        let path = url.strip_prefix("https://").unwrap_or(url);
+        HttpFetcher.fetch(&format!("{}/{}", self.url, path))
+    }
+}
+
+/// Answers all questions with yes
+struct AlwaysYes;
+
+impl Dialogs for AlwaysYes {
+    fn message(&self, _title: &str, _message: &str, _icon: MessageIcon) {}
+
+    fn confirm(&self, _title: &str, _message: &str, _icon: MessageIcon, _default: bool) -> bool {
+        true
+    }
+}
+
+pub struct Fixture {
+    pub server: FileServer,
+    pub fs: Arc<MemoryFs>,
+    pub env: Env,
+    pub game: SetupGame,
+}
+
+impl Fixture {
+    /// An empty game directory without the mod loader
+    pub fn new() -> Self {
+        static DIALOGS: Once = Once::new();
+        DIALOGS.call_once(|| ytinu_core::dialog::set_handler(AlwaysYes));
+
+        let server = FileServer::start();
+        let fs = Arc::new(MemoryFs::new());
+        fs.create_dir_all(Path::new(INSTALL_PATH)).unwrap();
+        let env = Env {
+            fs: fs.clone(),
+            fetcher: Arc::new(LocalFetcher { url: server.url() }),
+        };
+        let game: Game = serde_json::from_value(json!({
+            "id": "Desperados3",
+            "name": "Desperados III",
+            "appid": null,
+            "recommended_mods": [],
+        }))
+        .unwrap();
+        let game = SetupGame {
+            game,
+            install_path: INSTALL_PATH.to_string(),
+            mods: HashMap::new(),
+            bep_in_ex: None,
+            launches: Vec::new(),
+            declined_adoptions: HashSet::new(),
+            linked_mods: HashMap::new(),
+        };
+        Fixture {
+            server,
+            fs,
+            env,
+            game,
+        }
+    }
+
+    /// Files and directories in the game directory
+    pub fn tree(&self) -> Vec<String> {
+        self.fs.tree(INSTALL_PATH)
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.fs.exists(&Path::new(INSTALL_PATH).join(path))
+    }
+
+    pub fn read(&self, path: &str) -> Vec<u8> {
+        self.fs.read(&Path::new(INSTALL_PATH).join(path)).unwrap()
+    }
+
+    pub fn add_file(&self, path: &str, data: &[u8]) {
+        self.fs
+            .add_file(Path::new(INSTALL_PATH).join(path), data.to_vec());
+    }
+}
+
+/// A mod served by the file server at `https://mods.example/<file>`
+pub fn mod_entry(id: &str, version: &str, file: &str) -> Mod {
+    let url = format!("https://mods.example/{}", file);
+    serde_json::from_value(ytinu_test_utils::mod_entry(id, version, &url)).unwrap()
+}
@@ -0,0 +1,89 @@
+//! Deploying linked build directories without touching files that belong to the user.
+
+use std::path::{Path, PathBuf};
+
+use ytinu_core::{
+    data::ActionError,
+    dev_link::{LinkMode, LinkedMod},
+};
+
+/// Creates a build directory with a plugin and an empty game directory
+fn setup(test: &str) -> (PathBuf, PathBuf) {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("dev_link")
+        .join(test);
+    let _ = std::fs::remove_dir_all(&dir);
+    let build = dir.join("build");
+    let game = dir.join("game");
+    std::fs::create_dir_all(&build).unwrap();
+    std::fs::create_dir_all(game.join("BepInEx/plugins")).unwrap();
+    std::fs::write(build.join("Foo.dll"), "build").unwrap();
+    (build, game)
+}
+
+fn assert_conflict(result: anyhow::Result<()>) {
+    let error = result.unwrap_err();
+    assert!(
+        matches!(error.downcast_ref(), Some(ActionError::Conflict(_))),
+        "{:#}",
+        error
+    );
+}
+
+#[test]
+fn redeploy_replaces_own_deployment() {
+    let (build, game) = setup("redeploy");
+    let mut link = LinkedMod::new(&build, LinkMode::Copy, false).unwrap();
+    link.deploy(&game, "Foo").unwrap();
+    assert_eq!(link.deployed, vec!["BepInEx/plugins/Foo"]);
+
+    std::fs::write(build.join("Foo.dll"), "rebuilt").unwrap();
+    link.deploy(&game, "Foo").unwrap();
+    let deployed = game.join("BepInEx/plugins/Foo/Foo.dll");
+    assert_eq!(std::fs::read_to_string(deployed).unwrap(), "rebuilt");
+
+    link.undeploy(&game).unwrap();
+    assert!(!game.join("BepInEx/plugins/Foo").exists());
+}
+
+#[test]
+fn existing_plugin_directory_is_a_conflict() {
+    let (build, game) = setup("plugin_conflict");
+    let existing = game.join("BepInEx/plugins/Foo");
+    std::fs::create_dir_all(&existing).unwrap();
+    std::fs::write(existing.join("Settings.txt"), "user").unwrap();
+
+    let mut link = LinkedMod::new(&build, LinkMode::Copy, false).unwrap();
+    assert_conflict(link.deploy(&game, "Foo"));
+    assert!(link.deployed.is_empty());
+    assert_eq!(read_dir(&existing), vec!["Settings.txt"]);
+
+    // Nothing to remove, so the user's files survive unlinking
+    link.undeploy(&game).unwrap();
+    assert_eq!(read_dir(&existing), vec!["Settings.txt"]);
+}
+
+#[test]
+fn existing_script_is_a_conflict() {
+    let (build, game) = setup("script_conflict");
+    std::fs::write(build.join("Foo.pdb"), "symbols").unwrap();
+    let scripts = game.join("BepInEx/scripts");
+    std::fs::create_dir_all(&scripts).unwrap();
+    std::fs::write(scripts.join("Foo.dll"), "user").unwrap();
+
+    let mut link = LinkedMod::new(&build, LinkMode::Copy, true).unwrap();
+    assert_conflict(link.deploy(&game, "Foo"));
+    assert!(link.deployed.is_empty());
+    assert_eq!(read_dir(&scripts), vec!["Foo.dll"]);
+    let script = std::fs::read_to_string(scripts.join("Foo.dll")).unwrap();
+    assert_eq!(script, "user");
+}
+
+fn read_dir(dir: &Path) -> Vec<String> {
+    let mut names: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
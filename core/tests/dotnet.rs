@@ -0,0 +1,100 @@
+//! Reading plugin metadata from .NET assemblies.
+//!
+//! `fixtures/FooPlugin.dll` is a minimal hand-assembled assembly `Foo` 1.2.3.0 equivalent to:
+//!
+//! ```csharp
+//! namespace Example {
+//!     [BepInPlugin("com.example.foo", "Foo", "1.2.3")]
+//!     [BepInDependency("com.example.bar", "2.0")]
+//!     [BepInDependency("com.example.baz", BepInDependency.DependencyFlags.SoftDependency)]
+//!     [BepInProcess("Desperados III.exe")]
+//!     public class FooPlugin : BaseUnityPlugin {}
+//! }
+//! ```
+
+use ytinu_core::dotnet::parse_assembly;
+
+static PLUGIN: &[u8] = include_bytes!("fixtures/FooPlugin.dll");
+
+/// Offsets in the fixture
+static PE_HEADER: usize = 0x80;
+static CLI_DIRECTORY: usize = PE_HEADER + 24 + 96 + 14 * 8;
+static SECTION: usize = PE_HEADER + 24 + 0xE0;
+
+fn patched(offset: usize, value: u32) -> Vec<u8> {
+    let mut bytes = PLUGIN.to_vec();
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    bytes
+}
+
+#[test]
+fn reads_plugin_attributes() {
+    let assembly = parse_assembly(PLUGIN).unwrap();
+    assert_eq!(assembly.name.as_deref(), Some("Foo"));
+    assert_eq!(assembly.version.as_deref(), Some("1.2.3.0"));
+    assert_eq!(assembly.plugins.len(), 1);
+
+    let plugin = &assembly.plugins[0];
+    assert_eq!(plugin.type_name, "Example.FooPlugin");
+    assert_eq!(plugin.guid, "com.example.foo");
+    assert_eq!(plugin.name, "Foo");
+    assert_eq!(plugin.version, "1.2.3");
+    assert_eq!(plugin.processes, ["Desperados III.exe"]);
+
+    let dependencies: Vec<_> = plugin
+        .dependencies
+        .iter()
+        .map(|d| (d.guid.as_str(), d.soft, d.min_version.as_deref()))
+        .collect();
+    assert_eq!(
+        dependencies,
+        [
+            ("com.example.bar", false, Some("2.0")),
+            ("com.example.baz", true, None),
+        ]
+    );
+}
+
+#[test]
+fn rejects_files_that_are_not_assemblies() {
+    assert!(parse_assembly(b"").is_err());
+    assert!(parse_assembly(b"MZ").is_err());
+    assert!(parse_assembly(b"not a dll").is_err());
+    // A native DLL has no CLI header
+    assert!(parse_assembly(&patched(CLI_DIRECTORY, 0)).is_err());
+}
+
+#[test]
+fn rejects_truncated_assemblies() {
+    for len in 0..PLUGIN.len() {
+        if let Ok(assembly) = parse_assembly(&PLUGIN[..len]) {
+            // Only the zero padding at the end of the section may be missing
+            assert_eq!(assembly.plugins.len(), 1, "truncated to {} bytes", len);
+        }
+    }
+}
+
+#[test]
+fn rejects_out_of_range_addresses() {
+    // Section at the end of the address space
+    let mut bytes = patched(SECTION + 12, 0xFFFF_F000);
+    bytes[SECTION + 8..SECTION + 12].copy_from_slice(&0x2000u32.to_le_bytes());
+    bytes[CLI_DIRECTORY..CLI_DIRECTORY + 4].copy_from_slice(&0xFFFF_F010u32.to_le_bytes());
+    assert!(parse_assembly(&bytes).is_err());
+
+    // Raw data at the end of the file offsets
+    assert!(parse_assembly(&patched(SECTION + 20, 0xFFFF_FFF0)).is_err());
+    // PE header outside of the file
+    assert!(parse_assembly(&patched(0x3C, u32::MAX)).is_err());
+}
+
+#[test]
+fn corrupted_bytes_never_panic() {
+    for offset in 0..PLUGIN.len() {
+        for value in [0x00, 0x7F, 0xFF] {
+            let mut bytes = PLUGIN.to_vec();
+            bytes[offset] = value;
+            let _ = parse_assembly(&bytes);
+        }
+    }
+}
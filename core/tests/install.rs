@@ -0,0 +1,308 @@
+//! Install, update and remove flows of mods and the mod loader against an in-memory game
+//! directory and a local file server.
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::{mod_entry, Fixture};
+use ytinu_core::{
+    manager::{BEP_IN_EX_DOWNLOAD_URL, BEP_IN_EX_FILE_NAME},
+    plugin_config::ConfigEntryUpdate,
+};
+use ytinu_test_utils::zip;
+
+fn bep_in_ex_path() -> String {
+    format!("/{}", BEP_IN_EX_DOWNLOAD_URL.trim_start_matches("https://"))
+}
+
+fn with_mod_loader() -> Fixture {
+    let mut fixture = Fixture::new();
+    fixture.server.add(
+        &bep_in_ex_path(),
+        zip(&[
+            ("BepInEx/core/BepInEx.dll", b"bepinex"),
+            ("doorstop_config.ini", b"[UnityDoorstop]"),
+            ("winhttp.dll", b"doorstop"),
+        ]),
+    );
+    fixture.game.install_modloader(&fixture.env).unwrap();
+    fixture
+}
+
+#[test]
+fn install_modloader() {
+    let fixture = with_mod_loader();
+    assert_eq!(
+        fixture.tree(),
+        [
+            "BepInEx/",
+            "BepInEx/core/",
+            "BepInEx/core/BepInEx.dll",
+            "doorstop_config.ini",
+            "winhttp.dll",
+        ]
+    );
+    let bep_in_ex = fixture.game.bep_in_ex.as_ref().unwrap();
+    assert!(bep_in_ex.enabled);
+    assert_eq!(bep_in_ex.version.as_ref().unwrap().to_string(), "5.4.4");
+    // The archive is cached outside of the game directory
+    assert!(!fixture.exists(BEP_IN_EX_FILE_NAME));
+}
+
+#[test]
+fn install_modloader_twice_fails() {
+    let mut fixture = with_mod_loader();
+    assert!(fixture.game.install_modloader(&fixture.env).is_err());
+}
+
+#[test]
+fn reinstall_modloader_uses_cache() {
+    let mut fixture = with_mod_loader();
+    fixture.game.remove_modloader(&fixture.env).unwrap();
+    fixture.game.install_modloader(&fixture.env).unwrap();
+    let downloads = fixture
+        .server
+        .requests()
+        .iter()
+        .filter(|path| **path == bep_in_ex_path())
+        .count();
+    assert_eq!(downloads, 1);
+}
+
+#[test]
+fn disable_and_enable_modloader() {
+    let mut fixture = with_mod_loader();
+    fixture
+        .game
+        .set_modloader_enabled(&fixture.env, false)
+        .unwrap();
+    assert!(!fixture.exists("doorstop_config.ini"));
+    assert!(!fixture.exists("winhttp.dll"));
+    assert!(fixture.exists("BepInEx/doorstop_config.ini"));
+    assert!(fixture.exists("BepInEx/winhttp.dll"));
+    assert!(!fixture.game.bep_in_ex.as_ref().unwrap().enabled);
+
+    fixture
+        .game
+        .set_modloader_enabled(&fixture.env, true)
+        .unwrap();
+    assert!(fixture.exists("doorstop_config.ini"));
+    assert!(fixture.exists("winhttp.dll"));
+    assert!(fixture.game.bep_in_ex.as_ref().unwrap().enabled);
+}
+
+#[test]
+fn remove_modloader_removes_mods() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add("/mods.example/Foo.dll", "foo");
+    fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Foo", "1.0.0", "Foo.dll"))
+        .unwrap();
+
+    fixture.game.remove_modloader(&fixture.env).unwrap();
+    assert!(fixture.tree().is_empty());
+    assert!(fixture.game.bep_in_ex.is_none());
+    assert!(fixture.game.mods.is_empty());
+}
+
+#[test]
+fn detect_modloader_status() {
+    let mut fixture = with_mod_loader();
+    fixture.game.bep_in_ex = None;
+    fixture.game.update_modloader_status(&fixture.env);
+    // The unknown installation is removed after confirming, keeping configs and mods
+    assert!(!fixture.exists("BepInEx/core"));
+    assert!(fixture.exists("BepInEx"));
+
+    let mut fixture = with_mod_loader();
+    fixture.fs.add_file(
+        format!("{}/BepInEx/core/BepInEx.dll", common::INSTALL_PATH),
+        "changed",
+    );
+    fixture.game.update_modloader_status(&fixture.env);
+    // A changed BepInEx.dll means the version is unknown
+    assert!(fixture.game.bep_in_ex.as_ref().unwrap().version.is_none());
+}
+
+#[test]
+fn install_dll_mod() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add("/mods.example/Foo.dll", "foo v1");
+    fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Foo", "1.0.0", "Foo.dll"))
+        .unwrap();
+    assert_eq!(fixture.read("BepInEx/plugins/Foo.dll"), b"foo v1");
+    assert_eq!(fixture.game.mods["Foo"].version.to_string(), "1.0.0");
+}
+
+#[test]
+fn install_zip_mod() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add(
+        "/mods.example/Bar.zip",
+        zip(&[("Bar.dll", b"bar"), ("assets/data.bin", b"data")]),
+    );
+    fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Bar", "1.0.0", "Bar.zip"))
+        .unwrap();
+    assert_eq!(fixture.read("BepInEx/plugins/Bar/Bar.dll"), b"bar");
+    assert_eq!(fixture.read("BepInEx/plugins/Bar/assets/data.bin"), b"data");
+}
+
+#[test]
+fn install_zip_mod_to_root() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add(
+        "/mods.example/Root.zip",
+        zip(&[
+            ("BepInEx/plugins/Root.dll", b"root"),
+            ("Root.txt", b"readme"),
+        ]),
+    );
+    let mut m = mod_entry("Root", "1.0.0", "Root.zip");
+    m.extract_to_root = true;
+    m.files = Some(vec!["BepInEx/plugins/Root.dll".into(), "Root.txt".into()]);
+    fixture.game.install_mod(&fixture.env, m).unwrap();
+    assert_eq!(fixture.read("Root.txt"), b"readme");
+
+    fixture.game.remove_mod(&fixture.env, "Root").unwrap();
+    assert!(!fixture.exists("Root.txt"));
+    assert!(!fixture.exists("BepInEx/plugins/Root.dll"));
+    assert!(fixture.exists("BepInEx/core/BepInEx.dll"));
+}
+
+#[test]
+fn install_installed_mod_fails() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add("/mods.example/Foo.dll", "foo");
+    let m = mod_entry("Foo", "1.0.0", "Foo.dll");
+    fixture.game.install_mod(&fixture.env, m.clone()).unwrap();
+    assert!(fixture.game.install_mod(&fixture.env, m).is_err());
+}
+
+#[test]
+fn failed_download_installs_nothing() {
+    let mut fixture = with_mod_loader();
+    let before = fixture.tree();
+    let result = fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Missing", "1.0.0", "Missing.zip"));
+    assert!(result.is_err());
+    assert!(fixture.game.mods.is_empty());
+    assert_eq!(fixture.tree(), before);
+}
+
+#[test]
+fn unsafe_zip_paths_are_rejected() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add(
+        "/mods.example/Evil.zip",
+        zip(&[("../../Evil.dll", b"evil")]),
+    );
+    let result = fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Evil", "1.0.0", "Evil.zip"));
+    assert!(result.is_err());
+    assert!(fixture.game.mods.is_empty());
+}
+
+#[test]
+fn update_zip_mod() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add(
+        "/mods.example/Bar-1.zip",
+        zip(&[("Bar.dll", b"bar v1"), ("old.txt", b"old")]),
+    );
+    fixture
+        .server
+        .add("/mods.example/Bar-2.zip", zip(&[("Bar.dll", b"bar v2")]));
+    fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Bar", "1.0.0", "Bar-1.zip"))
+        .unwrap();
+
+    let new_meta = HashMap::from([("Bar".to_string(), mod_entry("Bar", "2.0.0", "Bar-2.zip"))]);
+    fixture.game.update_mods_meta(&new_meta);
+    fixture.game.update_mod(&fixture.env, "Bar").unwrap();
+    assert_eq!(fixture.read("BepInEx/plugins/Bar/Bar.dll"), b"bar v2");
+    assert!(!fixture.exists("BepInEx/plugins/Bar/old.txt"));
+    assert_eq!(fixture.game.mods["Bar"].version.to_string(), "2.0.0");
+}
+
+#[test]
+fn remove_mod_keeps_other_mods() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add("/mods.example/Foo.dll", "foo");
+    fixture
+        .server
+        .add("/mods.example/Bar.zip", zip(&[("Bar.dll", b"bar")]));
+    fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Foo", "1.0.0", "Foo.dll"))
+        .unwrap();
+    fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Bar", "1.0.0", "Bar.zip"))
+        .unwrap();
+
+    fixture.game.remove_mod(&fixture.env, "Bar").unwrap();
+    assert!(!fixture.exists("BepInEx/plugins/Bar"));
+    assert!(fixture.exists("BepInEx/plugins/Foo.dll"));
+    assert!(!fixture.game.mods.contains_key("Bar"));
+    assert!(fixture.game.mods.contains_key("Foo"));
+}
+
+#[test]
+fn plugin_scan_reads_the_game_directory() {
+    let mut fixture = with_mod_loader();
+    fixture.server.add("/mods.example/Foo.dll", "foo");
+    fixture
+        .game
+        .install_mod(&fixture.env, mod_entry("Foo", "1.0.0", "Foo.dll"))
+        .unwrap();
+    let plugins = format!("{}/BepInEx/plugins", common::INSTALL_PATH);
+    fixture
+        .fs
+        .add_file(format!("{}/Manual/Bar.dll", plugins), "bar");
+
+    let report = ytinu_core::plugins::scan(&*fixture.env.fs, &fixture.game, &HashMap::new());
+    let files: Vec<_> = report
+        .files
+        .iter()
+        .map(|file| (file.path.as_str(), file.mod_id.as_deref()))
+        .collect();
+    assert_eq!(files, [("Foo.dll", Some("Foo")), ("Manual/Bar.dll", None)]);
+    // Neither file is a .NET assembly
+    assert!(report.files.iter().all(|file| file.error.is_some()));
+}
+
+#[test]
+fn edit_plugin_config() {
+    let fixture = with_mod_loader();
+    let path = format!("{}/BepInEx/config/foo.cfg", common::INSTALL_PATH);
+    fixture.fs.add_file(
+        &path,
+        "[General]\n# Setting type: Boolean\n# Default value: true\nEnabled = true\n",
+    );
+
+    let configs = fixture.game.list_plugin_configs(&fixture.env).unwrap();
+    assert_eq!(configs.len(), 1);
+    assert_eq!(configs[0].file, "foo.cfg");
+    let update = ConfigEntryUpdate {
+        section: "General".to_string(),
+        key: "Enabled".to_string(),
+        value: Some("false".to_string()),
+    };
+    fixture
+        .game
+        .update_plugin_config(&fixture.env, "foo.cfg", update)
+        .unwrap();
+    assert_eq!(
+        fixture.read("BepInEx/config/foo.cfg"),
+        b"[General]\n# Setting type: Boolean\n# Default value: true\nEnabled = false\n"
+    );
+}
@@ -0,0 +1,52 @@
+//! Parsing `ytinu://` links from websites.
+
+use ytinu_core::install_link::InstallLink;
+
+fn parse(url: &str) -> anyhow::Result<InstallLink> {
+    url.parse()
+}
+
+#[test]
+fn links_with_and_without_version() {
+    let link = parse("ytinu://install/Desperados3/free-cam").unwrap();
+    assert_eq!(link.game, "Desperados3");
+    assert_eq!(link.mod_id, "free-cam");
+    assert_eq!(link.version, None);
+
+    let link = parse("ytinu://install/Desperados3/free-cam@1.2.0-beta.1").unwrap();
+    assert_eq!(link.mod_id, "free-cam");
+    assert_eq!(link.version, Some("1.2.0-beta.1".parse().unwrap()));
+}
+
+#[test]
+fn browsers_may_change_scheme_case_and_add_a_slash() {
+    let link = parse("YTINU://install/Desperados3/free-cam/").unwrap();
+    assert_eq!(link.to_string(), "ytinu://install/Desperados3/free-cam");
+}
+
+#[test]
+fn links_are_displayed_as_parsed() {
+    let url = "ytinu://install/Desperados3/free-cam@1.2.0";
+    assert_eq!(parse(url).unwrap().to_string(), url);
+}
+
+#[test]
+fn invalid_links_are_rejected() {
+    for url in [
+        "",
+        "ytinu:",
+        "https://install/Desperados3/free-cam",
+        "ytinu://",
+        "ytinu://remove/Desperados3/free-cam",
+        "ytinu://install/Desperados3",
+        "ytinu://install/Desperados3/free-cam/extra",
+        "ytinu://install/Desperados3/..",
+        "ytinu://install/../free-cam",
+        "ytinu://install/Desperados3/free cam",
+        "ytinu://install/Desperados3/free-cam@latest",
+        "ytinu://install/Desperados3/@1.0.0",
+        "ytinu://install//free-cam",
+    ] {
+        assert!(parse(url).is_err(), "{} was accepted", url);
+    }
+}
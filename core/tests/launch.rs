@@ -0,0 +1,34 @@
+//! Launching the game executable directly and recording the launch.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{os::unix::fs::PermissionsExt, path::PathBuf};
+
+use common::Fixture;
+use ytinu_core::launch::{launch, Launch, LaunchOptions};
+
+#[test]
+fn launch_without_mod_loader_is_recorded_as_vanilla() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("launch")
+        .join("launch_without_mod_loader_is_recorded_as_vanilla");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let exe = dir.join("Desperados III.x86_64");
+    std::fs::write(&exe, "#!/bin/sh\nexit 3\n").unwrap();
+    std::fs::set_permissions(&exe, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut game = Fixture::new().game;
+    game.install_path = dir.to_string_lossy().to_string();
+    assert!(game.bep_in_ex.is_none());
+
+    let record = match launch(&game, LaunchOptions::default()).unwrap() {
+        Launch::Process(running) => running.wait(),
+        Launch::Steam(_) => panic!("Launched through Steam"),
+    };
+    assert!(record.vanilla);
+    assert!(!record.steam);
+    assert_eq!(record.exit_code, Some(3));
+}
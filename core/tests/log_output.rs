@@ -0,0 +1,152 @@
+//! Parsing `LogOutput.log` and attributing entries and exceptions to installed mods.
+
+mod common;
+
+use common::{mod_entry, Fixture};
+use ytinu_core::{
+    data::InstalledMod,
+    log_output::{parse, read},
+};
+
+static LOG: &str = "\
+[Message:   BepInEx] BepInEx 5.4.4.0 - Desperados III
+[Info   :   BepInEx] Loading [Better Foo 1.0.0]
+[Info   :Better Foo] Loaded
+[Error  :Better Foo] NullReferenceException: Object reference not set to an instance of an object
+  at Foo.Patches.Run () [0x00000] in <6d1f>:0
+[Error  : Unity Log] InvalidOperationException: Sequence contains no elements
+Stack trace:
+FooCore.Internal.Spawner.Update () (at <9a2b>:0)
+[Error  : Unity Log] ArgumentException: Value does not fall within the expected range.
+  at Stranger.Lib.Run () [0x00000] in <7c3d>:0
+[Warning:  Stranger] Something odd
+[Trace  :  Stranger] Not a known level
+";
+
+static PLUGIN: &[u8] = include_bytes!("fixtures/FooPlugin.dll");
+
+fn install(fixture: &mut Fixture, id: &str, name: &str, files: &[&str]) {
+    let mut m = mod_entry(id, "1.0.0", &format!("{}.zip", id));
+    m.name = name.to_string();
+    fixture.game.mods.insert(
+        id.to_string(),
+        InstalledMod {
+            version: m.version.clone(),
+            m,
+            enabled: true,
+            files: Some(files.iter().map(|f| f.to_string()).collect()),
+        },
+    );
+}
+
+/// A game with `Foo` installed to `BepInEx/plugins/Foo`, containing `FooCore.dll`
+fn game_with_log(log: &str) -> Fixture {
+    let mut fixture = Fixture::new();
+    fixture.add_file("BepInEx/plugins/Foo/FooCore.dll", b"");
+    fixture.add_file("BepInEx/LogOutput.log", log.as_bytes());
+    install(&mut fixture, "Foo", "Better Foo", &["BepInEx/plugins/Foo"]);
+    fixture
+}
+
+#[test]
+fn parse_multi_line_entries() {
+    let entries = parse(LOG);
+    let headers: Vec<_> = entries
+        .iter()
+        .map(|e| (e.level.as_str(), e.source.as_str(), e.exception))
+        .collect();
+    assert_eq!(
+        headers,
+        [
+            ("Message", "BepInEx", false),
+            ("Info", "BepInEx", false),
+            ("Info", "Better Foo", false),
+            ("Error", "Better Foo", true),
+            ("Error", "Unity Log", true),
+            ("Error", "Unity Log", true),
+            ("Warning", "Stranger", false),
+        ]
+    );
+    assert_eq!(
+        entries[4].message,
+        "InvalidOperationException: Sequence contains no elements\n\
+         Stack trace:\n\
+         FooCore.Internal.Spawner.Update () (at <9a2b>:0)"
+    );
+    // Lines with an unknown level belong to the previous entry
+    assert!(entries[6]
+        .message
+        .ends_with("\n[Trace  :  Stranger] Not a known level"));
+}
+
+#[test]
+fn lines_before_the_first_entry_are_skipped() {
+    let entries = parse("  at Cut.Off () [0x00000]\n[Info   :   BepInEx] Started\n");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].message, "Started");
+    assert!(!entries[0].exception);
+}
+
+#[test]
+fn attribute_entries_to_installed_mods() {
+    let fixture = game_with_log(LOG);
+    let log = read(&*fixture.env.fs, &fixture.game, 1000).unwrap();
+    let mod_ids: Vec<_> = log.entries.iter().map(|e| e.mod_id.as_deref()).collect();
+    assert_eq!(
+        mod_ids,
+        [
+            None,
+            None,
+            // By the mod name as source
+            Some("Foo"),
+            Some("Foo"),
+            // By the DLL name in the stack trace
+            Some("Foo"),
+            // Unknown sources and namespaces
+            None,
+            None,
+        ]
+    );
+    assert_eq!(log.exceptions.get("Foo"), Some(&2));
+    assert_eq!(log.exceptions.len(), 1);
+    assert_eq!(log.unattributed_exceptions, 1);
+}
+
+#[test]
+fn attribute_entries_by_plugin_metadata() {
+    // The `Foo` plugin in namespace `Example` ships in a DLL with an unrelated name
+    let mut fixture = game_with_log(
+        "[Info   :       Foo] Loaded\n\
+         [Error  : Unity Log] NullReferenceException: Object reference not set\n\
+         \x20 at Example.Patches.Run () [0x00000] in <6d1f>:0\n",
+    );
+    fixture.add_file("BepInEx/plugins/renamed.dll", PLUGIN);
+    install(
+        &mut fixture,
+        "Bar",
+        "Unrelated Name",
+        &["BepInEx/plugins/renamed.dll"],
+    );
+
+    let log = read(&*fixture.env.fs, &fixture.game, 1000).unwrap();
+    let mod_ids: Vec<_> = log.entries.iter().map(|e| e.mod_id.as_deref()).collect();
+    assert_eq!(mod_ids, [Some("Bar"), Some("Bar")]);
+    assert_eq!(log.exceptions.get("Bar"), Some(&1));
+}
+
+#[test]
+fn only_the_last_entries_are_returned() {
+    let fixture = game_with_log(LOG);
+    let log = read(&*fixture.env.fs, &fixture.game, 2).unwrap();
+    assert_eq!(log.entries.len(), 2);
+    assert_eq!(log.entries[1].source, "Stranger");
+    assert_eq!(log.unattributed_exceptions, 1);
+    assert!(log.exceptions.is_empty());
+}
+
+#[test]
+fn missing_log_file() {
+    let fixture = Fixture::new();
+    let error = read(&*fixture.env.fs, &fixture.game, 1000).unwrap_err();
+    assert!(format!("{:#}", error).contains("start the game once"));
+}
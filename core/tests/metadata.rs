@@ -0,0 +1,54 @@
+//! Parsing the metadata leniently so one invalid entry doesn't drop the whole catalog.
+
+use serde_json::json;
+use ytinu_core::data::{Metadata, MetadataIn, STABLE_CHANNEL};
+
+fn parse(meta: serde_json::Value) -> Metadata {
+    serde_json::from_value::<MetadataIn>(meta).unwrap().into()
+}
+
+#[test]
+fn skip_invalid_release() {
+    let meta = parse(json!({
+        "format": 2,
+        "releases": {
+            "stable": { "version": "1.2.0", "downloads": {} },
+            "beta": { "version": "not a version", "downloads": {} },
+        },
+        "messages": [],
+        "games": [],
+        "mods": [],
+    }));
+    let stable = meta.release(STABLE_CHANNEL).unwrap();
+    assert_eq!(stable.version, semver::Version::new(1, 2, 0));
+    assert!(meta.release("beta").is_none());
+    assert_eq!(meta.warnings.len(), 1, "{:?}", meta.warnings);
+    assert!(meta.warnings[0].starts_with("Skipped invalid release 'beta'"));
+}
+
+#[test]
+fn skip_invalid_changelog_entry() {
+    let meta = parse(json!({
+        "format": 2,
+        "releases": { "stable": { "version": "1.2.0", "downloads": {} } },
+        "changelog": [
+            { "version": "1.1.0", "notes": "Fixes" },
+            { "version": "1.0", "notes": "Incomplete version" },
+            { "version": "1.0.0" },
+        ],
+        "messages": [],
+        "games": [],
+        "mods": [],
+    }));
+    let versions: Vec<_> = meta
+        .changelog
+        .iter()
+        .map(|e| e.version.to_string())
+        .collect();
+    assert_eq!(versions, vec!["1.1.0"]);
+    assert_eq!(meta.warnings.len(), 2, "{:?}", meta.warnings);
+    assert!(meta
+        .warnings
+        .iter()
+        .all(|warning| warning.starts_with("Skipped invalid changelog entry")));
+}
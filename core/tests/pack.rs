@@ -0,0 +1,80 @@
+//! Building release zips and their metadata entries with `ytinu-cli pack`.
+
+use std::{io::Cursor, path::PathBuf};
+
+use ytinu_core::pack::{pack, PackOptions};
+
+static PLUGIN: &[u8] = include_bytes!("fixtures/FooPlugin.dll");
+static URL: &str = "https://mods.example/Foo-1.2.3.zip";
+
+/// A mod directory with the `Foo` 1.2.3 plugin and an asset, and an empty output directory
+fn mod_dir(test: &str) -> (PathBuf, PathBuf) {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("pack")
+        .join(test);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("Foo/assets")).unwrap();
+    std::fs::create_dir_all(dir.join("out")).unwrap();
+    std::fs::write(dir.join("Foo/Foo.dll"), PLUGIN).unwrap();
+    std::fs::write(dir.join("Foo/assets/icon.png"), "icon").unwrap();
+    (dir.join("Foo"), dir.join("out"))
+}
+
+#[test]
+fn pack_mod_directory() {
+    let (path, out) = mod_dir("pack_mod_directory");
+    let package = pack(&PackOptions {
+        path,
+        out: Some(out.clone()),
+        url: Some(URL.to_string()),
+        ..PackOptions::default()
+    })
+    .unwrap();
+
+    assert_eq!(
+        package.zip,
+        out.canonicalize().unwrap().join("Foo-1.2.3.zip")
+    );
+    let data = std::fs::read(&package.zip).unwrap();
+    let archive = zip::ZipArchive::new(Cursor::new(&data)).unwrap();
+    let mut names: Vec<_> = archive.file_names().collect();
+    names.sort_unstable();
+    assert_eq!(names, ["Foo.dll", "assets/icon.png"]);
+
+    let entry: serde_json::Value = serde_json::from_str(&package.entry_json().unwrap()).unwrap();
+    assert_eq!(
+        entry,
+        serde_json::json!({
+            "id": "Foo",
+            "name": "Foo",
+            "version": "1.2.3",
+            "download": URL,
+            "extract_to_root": false,
+            "dev_mod": false,
+            "files": ["BepInEx/plugins/Foo"],
+            "hash": ytinu_core::utils::hash(&data),
+            "size": data.len(),
+        })
+    );
+}
+
+#[test]
+fn download_url_is_required() {
+    let (path, out) = mod_dir("download_url_is_required");
+    let options = PackOptions {
+        path,
+        out: Some(out.clone()),
+        ..PackOptions::default()
+    };
+    let error = pack(&options).err().unwrap();
+    assert!(error.to_string().contains("--url"), "{}", error);
+
+    for url in ["Foo-1.2.3.zip", "file:///tmp/Foo-1.2.3.zip"] {
+        let options = PackOptions {
+            url: Some(url.to_string()),
+            ..options.clone()
+        };
+        assert!(pack(&options).is_err(), "{}", url);
+    }
+    assert_eq!(std::fs::read_dir(&out).unwrap().count(), 0);
+}
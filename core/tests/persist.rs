@@ -0,0 +1,197 @@
+//! Storing JSON files with backups, recovering from damaged files and upgrading old formats.
+
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+use ytinu_core::{
+    data::State,
+    migrations::STATE_MIGRATIONS,
+    persist::{JsonFile, Loaded, Migration},
+};
+
+/// Format 0 stored a plain number, format 1 an object and format 2 renamed its field
+static MIGRATIONS: &[Migration] = &[
+    |data| Ok(json!({ "value": data })),
+    |data| Ok(json!({ "count": data["value"] })),
+];
+
+fn json_file(test: &str, backups: usize) -> JsonFile {
+    json_file_with(test, backups, &[])
+}
+
+fn json_file_with(test: &str, backups: usize, migrations: &'static [Migration]) -> JsonFile {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("persist")
+        .join(test);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    JsonFile::new(dir.join("data.json"), backups, migrations)
+}
+
+fn read(path: impl Into<PathBuf>) -> String {
+    std::fs::read_to_string(path.into()).unwrap()
+}
+
+fn read_json(path: impl Into<PathBuf>) -> Value {
+    serde_json::from_str(&read(path)).unwrap()
+}
+
+/// The stored data without the format
+fn stored(path: impl Into<PathBuf>) -> String {
+    read_json(path)["data"].to_string()
+}
+
+fn loaded(file: &JsonFile) -> Loaded<u32> {
+    file.load()
+}
+
+#[test]
+fn store_rotates_backups() {
+    let file = json_file("store_rotates_backups", 3);
+    for value in 1..=6 {
+        file.store(&value).unwrap();
+    }
+    assert_eq!(read_json(file.path()), json!({ "format": 0, "data": 6 }));
+    assert_eq!(stored(file.backup_path(1)), "5");
+    assert_eq!(stored(file.backup_path(3)), "3");
+    assert!(!file.backup_path(4).exists());
+    assert!(!file.path().with_file_name("data.json.tmp").exists());
+}
+
+#[test]
+fn unchanged_value_is_not_backed_up() {
+    let file = json_file("unchanged_value_is_not_backed_up", 3);
+    file.store(&1).unwrap();
+    file.store(&2).unwrap();
+    file.store(&2).unwrap();
+    assert_eq!(stored(file.backup_path(1)), "1");
+    assert!(!file.backup_path(2).exists());
+}
+
+#[test]
+fn missing_file() {
+    let file = json_file("missing_file", 3);
+    assert!(matches!(loaded(&file), Loaded::Missing));
+}
+
+#[test]
+fn damaged_file_is_recovered_from_newest_valid_backup() {
+    let file = json_file("damaged_file_is_recovered_from_newest_valid_backup", 3);
+    for value in 1..=3 {
+        file.store(&value).unwrap();
+    }
+    std::fs::write(file.path(), "{").unwrap();
+    std::fs::write(file.backup_path(1), "").unwrap();
+
+    match loaded(&file) {
+        Loaded::Recovered { value, backup, .. } => {
+            assert_eq!(value, 1);
+            assert_eq!(backup, file.backup_path(2));
+        }
+        _ => panic!("Expected recovery from a backup"),
+    }
+    // The damaged file is kept and not rotated into the backups by the next store
+    assert_eq!(read(file.broken_path()), "{");
+    file.store(&4).unwrap();
+    assert_eq!(read(file.backup_path(1)), "");
+}
+
+#[test]
+fn lost_file_is_recovered_from_legacy_backup() {
+    let file = json_file("lost_file_is_recovered_from_legacy_backup", 3);
+    std::fs::write(file.path().with_file_name("data.json.bkp"), "7").unwrap();
+    assert!(matches!(loaded(&file), Loaded::Recovered { value: 7, .. }));
+}
+
+#[test]
+fn unreadable_file_and_backups_fail() {
+    let file = json_file("unreadable_file_and_backups_fail", 3);
+    file.store(&1).unwrap();
+    file.store(&2).unwrap();
+    std::fs::write(file.path(), "garbage").unwrap();
+    std::fs::write(file.backup_path(1), "garbage").unwrap();
+    assert!(matches!(loaded(&file), Loaded::Failed(_)));
+    assert_eq!(read(file.broken_path()), "garbage");
+}
+
+#[test]
+fn old_formats_are_upgraded() {
+    let file = json_file_with("old_formats_are_upgraded", 3, MIGRATIONS);
+    std::fs::write(file.path(), "5").unwrap();
+    let value: Value = match file.load() {
+        Loaded::Ok(value) => value,
+        _ => panic!("Expected the upgraded file"),
+    };
+    assert_eq!(value, json!({ "count": 5 }));
+    assert_eq!(read(file.pre_migration_path(0)), "5");
+
+    std::fs::write(file.path(), r#"{"format":1,"data":{"value":7}}"#).unwrap();
+    assert!(matches!(file.load::<Value>(), Loaded::Ok(value) if value == json!({ "count": 7 })));
+    assert!(file.pre_migration_path(1).exists());
+
+    file.store(&value).unwrap();
+    assert_eq!(
+        read_json(file.path()),
+        json!({ "format": 2, "data": { "count": 5 } })
+    );
+}
+
+#[test]
+fn newer_format_is_not_loaded() {
+    let file = json_file_with("newer_format_is_not_loaded", 3, MIGRATIONS);
+    file.store(&json!({ "count": 1 })).unwrap();
+    file.store(&json!({ "count": 2 })).unwrap();
+    let newer = r#"{"format":3,"data":{}}"#;
+    std::fs::write(file.path(), newer).unwrap();
+    match file.load::<Value>() {
+        Loaded::Unusable(error) => assert!(format!("{:#}", error).contains("newer version")),
+        _ => panic!("Expected the file to be rejected"),
+    }
+    // Neither replaced by a backup nor moved away
+    assert_eq!(read(file.path()), newer);
+    assert!(!file.broken_path().exists());
+}
+
+#[test]
+fn unreadable_file_is_not_recovered() {
+    let file = json_file("unreadable_file_is_not_recovered", 3);
+    file.store(&1).unwrap();
+    file.store(&2).unwrap();
+    // Reading a directory fails with an I/O error instead of returning damaged data
+    std::fs::remove_file(file.path()).unwrap();
+    std::fs::create_dir(file.path()).unwrap();
+    assert!(matches!(loaded(&file), Loaded::Unusable(_)));
+    assert!(file.path().is_dir());
+    assert!(!file.broken_path().exists());
+}
+
+#[test]
+fn old_backup_is_upgraded_next_to_itself() {
+    let file = json_file_with("old_backup_is_upgraded_next_to_itself", 3, MIGRATIONS);
+    std::fs::write(file.path(), "{").unwrap();
+    std::fs::write(file.backup_path(1), "5").unwrap();
+    assert!(matches!(
+        file.load::<Value>(),
+        Loaded::Recovered { value, .. } if value == json!({ "count": 5 })
+    ));
+    assert_eq!(read(file.backup_path(1).with_extension("1.format0")), "5");
+    assert!(!file.pre_migration_path(0).exists());
+}
+
+#[test]
+fn unversioned_data_json_is_upgraded() {
+    let file = json_file_with("unversioned_data_json_is_upgraded", 3, STATE_MIGRATIONS);
+    let data = json!({
+        "selected_game": "Desperados3",
+        "games": {},
+        "shown_messages": ["welcome"],
+    });
+    std::fs::write(file.path(), data.to_string()).unwrap();
+    let state: State = match file.load() {
+        Loaded::Ok(state) => state,
+        _ => panic!("Expected the upgraded file"),
+    };
+    assert_eq!(state.selected_game.as_deref(), Some("Desperados3"));
+    assert!(state.shown_messages.contains("welcome"));
+    assert!(file.pre_migration_path(0).exists());
+}
@@ -0,0 +1,125 @@
+//! Editing BepInEx config files without changing anything but the edited values.
+
+use ytinu_core::plugin_config::{ConfigEntryUpdate, PluginConfig};
+
+/// Written by BepInEx 5.4: BOM, CRLF line endings and a trailing space after empty defaults
+static CONFIG: &str = "\u{feff}## Settings file was created by plugin Foo v1.2.3\r\n\
+## Plugin GUID: com.example.foo\r\n\
+\r\n\
+[General]\r\n\
+\r\n\
+## Whether the mod is enabled\r\n\
+# Setting type: Boolean\r\n\
+# Default value: true\r\n\
+Enabled = false\r\n\
+\r\n\
+## Speed multiplier\r\n\
+# Setting type: Single\r\n\
+# Default value: 1\r\n\
+# Acceptable value range: From 0.5 to 4\r\n\
+Speed = 2\r\n\
+\r\n\
+[Paths]\r\n\
+\r\n\
+## Where screenshots are saved\r\n\
+# Setting type: String\r\n\
+# Default value: \r\n\
+Folder = C:\\\\Games\\\\Shots\r\n\
+\r\n\
+## Shown on start\r\n\
+# Setting type: String\r\n\
+# Default value: Say \\\"hi\\\"\r\n\
+Greeting = Say \\\"hello\\\"\r\n";
+
+fn update(config: &mut PluginConfig, section: &str, key: &str, value: Option<&str>) {
+    config
+        .update(ConfigEntryUpdate {
+            section: section.to_string(),
+            key: key.to_string(),
+            value: value.map(str::to_string),
+        })
+        .unwrap();
+}
+
+fn value<'a>(config: &'a PluginConfig, key: &str) -> &'a str {
+    let entry = config.entries().iter().find(|e| e.key == key).unwrap();
+    &entry.value
+}
+
+#[test]
+fn unchanged_config_is_written_back_as_read() {
+    let config = PluginConfig::parse(CONFIG);
+    assert_eq!(config.to_string(), CONFIG);
+    assert_eq!(
+        config.plugin_info(),
+        (
+            Some("Foo v1.2.3".to_string()),
+            Some("com.example.foo".to_string())
+        )
+    );
+}
+
+#[test]
+fn update_only_changes_the_value() {
+    let mut config = PluginConfig::parse(CONFIG);
+    update(&mut config, "General", "Enabled", Some("true"));
+    // Resets to the default value
+    update(&mut config, "General", "Speed", None);
+
+    let expected = CONFIG
+        .replace("Enabled = false", "Enabled = true")
+        .replace("Speed = 2", "Speed = 1");
+    assert_eq!(config.to_string(), expected);
+    assert_eq!(value(&config, "Speed"), "1");
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    let mut config = PluginConfig::parse(CONFIG);
+    for (key, value) in [("Enabled", "yes"), ("Speed", "5"), ("Speed", "fast")] {
+        let update = ConfigEntryUpdate {
+            section: "General".to_string(),
+            key: key.to_string(),
+            value: Some(value.to_string()),
+        };
+        assert!(config.update(update).is_err(), "{} = {}", key, value);
+    }
+    assert_eq!(config.to_string(), CONFIG);
+}
+
+#[test]
+fn string_values_are_escaped() {
+    let mut config = PluginConfig::parse(CONFIG);
+    assert_eq!(value(&config, "Folder"), r"C:\Games\Shots");
+    assert_eq!(value(&config, "Greeting"), r#"Say "hello""#);
+    let greeting = config.entries().iter().find(|e| e.key == "Greeting");
+    assert_eq!(
+        greeting.unwrap().default_value.as_deref(),
+        Some(r#"Say "hi""#)
+    );
+
+    update(&mut config, "Paths", "Folder", Some(r"D:\Shots"));
+    update(&mut config, "Paths", "Greeting", Some(r#"a\b "c""#));
+    let text = config.to_string();
+    assert!(text.contains("\r\nFolder = D:\\\\Shots\r\n"), "{}", text);
+    assert!(
+        text.contains("\r\nGreeting = a\\\\b \\\"c\\\"\r\n"),
+        "{}",
+        text
+    );
+
+    let config = PluginConfig::parse(&text);
+    assert_eq!(value(&config, "Folder"), r"D:\Shots");
+    assert_eq!(value(&config, "Greeting"), r#"a\b "c""#);
+
+    let mut config = PluginConfig::parse(&text);
+    update(&mut config, "Paths", "Greeting", None);
+    assert!(config.to_string().contains("Greeting = Say \\\"hi\\\"\r\n"));
+}
+
+#[test]
+fn unescaped_paths_are_kept() {
+    // BepInEx reads paths written by hand with single backslashes as they are
+    let config = PluginConfig::parse("[Paths]\n# Setting type: String\nFolder = C:\\Games\\tmp\n");
+    assert_eq!(value(&config, "Folder"), r"C:\Games\tmp");
+}
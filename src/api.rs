@@ -0,0 +1,254 @@
+//! Routes and errors of the HTTP API used by the UI.
+//!
+//! All routes are relative to `/api/`. `game` refers to the selected game. GET requests never
+//! change any state.
+//!
+//! | Method | Route                      | Action                                  |
+//! |--------|----------------------------|-----------------------------------------|
+//! | GET    | `state`                    | Setup games and app state               |
+//! | GET    | `metadata`                 | Loaded metadata                         |
+//! | GET    | `config`                   | Settings                                |
+//! | PUT    | `config`                   | Change settings                         |
+//! | GET    | `update`                   | Available ytinu update                  |
+//! | POST   | `update`                   | Install the available update            |
+//! | PUT    | `update/skipped`           | Skip an update version or `null`        |
+//! | POST   | `shutdown`                 | Stop the server                         |
+//! | POST   | `activate`                 | Show the UI for a later launch of ytinu |
+//! | GET    | `dialogs/directory?path=`  | Let the user select a directory         |
+//! | GET    | `dialogs/file?path=`       | Let the user select a mod file          |
+//! | GET    | `games/detect`             | Detect the game installation directory  |
+//! | POST   | `games`                    | Set up a game from its install path     |
+//! | PUT    | `game/install_path`        | Change the install path                 |
+//! | POST   | `game/loader`              | Install the mod loader                  |
+//! | DELETE | `game/loader`              | Remove the mod loader and all mods      |
+//! | PUT    | `game/loader/enabled`      | Enable or disable the mod loader        |
+//! | POST   | `game/launch`              | Launch the game                         |
+//! | POST   | `game/open/{dir}`          | Open `game`, `mods` or `config` dir     |
+//! | GET    | `game/plugins`             | Installed plugins and problems          |
+//! | GET    | `game/log?limit=`          | BepInEx log output                      |
+//! | GET    | `game/configs`             | Plugin config files                     |
+//! | GET    | `game/configs/{file}`      | Entries of a plugin config file         |
+//! | PATCH  | `game/configs/{file}`      | Change a plugin config entry            |
+//! | POST   | `game/mods`                | Install a mod from a local file         |
+//! | PUT    | `game/mods/{id}`           | Install a mod from the metadata         |
+//! | DELETE | `game/mods/{id}`           | Uninstall a mod                         |
+//! | POST   | `game/mods/{id}/update`    | Update a mod                            |
+//! | POST   | `game/mods/{id}/adopt`     | Manage a manually installed mod         |
+//! | POST   | `game/links`               | Link a local build directory            |
+//! | DELETE | `game/links/{id}`          | Remove a linked mod                     |
+//!
+//! Errors are returned with a matching status code and an [`ApiError`] body.
+//! `schema/openapi.json` describes the request and response bodies (see `src/schema.rs`).
+
+use std::fmt;
+
+use rouille::Response;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use ytinu_core::data::ActionError;
+
+static METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route<'a> {
+    State,
+    Metadata,
+    Config,
+    SetConfig,
+    UpdateInfo,
+    InstallUpdate,
+    SkipUpdate,
+    Shutdown,
+    Activate,
+    BrowseDirectory,
+    BrowseFile,
+    DetectGame,
+    AddGame,
+    SetInstallPath,
+    InstallLoader,
+    RemoveLoader,
+    SetLoaderEnabled,
+    Launch,
+    OpenDir(&'a str),
+    Plugins,
+    Log,
+    PluginConfigs,
+    PluginConfig(&'a str),
+    SetPluginConfig(&'a str),
+    InstallLocalMod,
+    InstallMod(&'a str),
+    RemoveMod(&'a str),
+    UpdateMod(&'a str),
+    AdoptMod(&'a str),
+    LinkMod,
+    UnlinkMod(&'a str),
+}
+
+impl<'a> Route<'a> {
+    /// Finds the route of a request. Fails with `not_found` for unknown paths and
+    /// `method_not_allowed` if the path only exists for other methods.
+    pub fn parse(method: &str, path: &'a str) -> Result<Self, ApiError> {
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        if let Some(route) = Self::find(method, &segments) {
+            return Ok(route);
+        }
+        let allowed: Vec<&str> = METHODS
+            .iter()
+            .copied()
+            .filter(|other| Self::find(other, &segments).is_some())
+            .collect();
+        if allowed.is_empty() {
+            Err(ApiError::new(
+                ErrorCode::NotFound,
+                format!("Unknown API endpoint: '{}'", path),
+            ))
+        } else {
+            Err(ApiError::new(
+                ErrorCode::MethodNotAllowed,
+                format!(
+                    "{} not allowed for '{}'. Allowed: {}",
+                    method,
+                    path,
+                    allowed.join(", ")
+                ),
+            ))
+        }
+    }
+
+    fn find(method: &str, segments: &[&'a str]) -> Option<Self> {
+        use Route::*;
+        let route = match (method, segments) {
+            ("GET", ["state"]) => State,
+            ("GET", ["metadata"]) => Metadata,
+            ("GET", ["config"]) => Config,
+            ("PUT", ["config"]) => SetConfig,
+            ("GET", ["update"]) => UpdateInfo,
+            ("POST", ["update"]) => InstallUpdate,
+            ("PUT", ["update", "skipped"]) => SkipUpdate,
+            ("POST", ["shutdown"]) => Shutdown,
+            ("POST", ["activate"]) => Activate,
+            ("GET", ["dialogs", "directory"]) => BrowseDirectory,
+            ("GET", ["dialogs", "file"]) => BrowseFile,
+            ("GET", ["games", "detect"]) => DetectGame,
+            ("POST", ["games"]) => AddGame,
+            ("PUT", ["game", "install_path"]) => SetInstallPath,
+            ("POST", ["game", "loader"]) => InstallLoader,
+            ("DELETE", ["game", "loader"]) => RemoveLoader,
+            ("PUT", ["game", "loader", "enabled"]) => SetLoaderEnabled,
+            ("POST", ["game", "launch"]) => Launch,
+            ("POST", ["game", "open", dir]) => OpenDir(dir),
+            ("GET", ["game", "plugins"]) => Plugins,
+            ("GET", ["game", "log"]) => Log,
+            ("GET", ["game", "configs"]) => PluginConfigs,
+            ("GET", ["game", "configs", file]) => PluginConfig(file),
+            ("PATCH", ["game", "configs", file]) => SetPluginConfig(file),
+            ("POST", ["game", "mods"]) => InstallLocalMod,
+            ("PUT", ["game", "mods", id]) => InstallMod(id),
+            ("DELETE", ["game", "mods", id]) => RemoveMod(id),
+            ("POST", ["game", "mods", id, "update"]) => UpdateMod(id),
+            ("POST", ["game", "mods", id, "adopt"]) => AdoptMod(id),
+            ("POST", ["game", "links"]) => LinkMod,
+            ("DELETE", ["game", "links", id]) => UnlinkMod(id),
+            _ => return None,
+        };
+        Some(route)
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Missing or malformed request body or parameters
+    BadRequest,
+    /// Missing session token or request from another website
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    /// The action conflicts with the current state, e.g. the mod is already installed
+    Conflict,
+    NoGameSelected,
+    /// The game is running and the user chose not to modify its files
+    GameRunning,
+    /// The user cancelled the action in a confirmation dialog
+    Cancelled,
+    /// The action failed, e.g. because a download or file operation failed
+    Failed,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> u16 {
+        match self {
+            ErrorCode::BadRequest => 400,
+            ErrorCode::Forbidden => 403,
+            ErrorCode::NotFound => 404,
+            ErrorCode::MethodNotAllowed => 405,
+            ErrorCode::Conflict
+            | ErrorCode::NoGameSelected
+            | ErrorCode::GameRunning
+            | ErrorCode::Cancelled => 409,
+            ErrorCode::Failed => 500,
+        }
+    }
+}
+
+/// Error response body
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    /// Message for the user
+    pub error: String,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, error: impl Into<String>) -> Self {
+        Self {
+            code,
+            error: error.into(),
+        }
+    }
+
+    pub fn bad_request(error: impl Into<String>) -> Self {
+        Self::new(ErrorCode::BadRequest, error)
+    }
+
+    pub fn not_found(error: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, error)
+    }
+
+    pub fn conflict(error: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, error)
+    }
+
+    pub fn no_game_selected() -> Self {
+        Self::new(
+            ErrorCode::NoGameSelected,
+            ActionError::NoGameSelected.to_string(),
+        )
+    }
+
+    pub fn response(&self) -> Response {
+        Response::json(self).with_status_code(self.code.status())
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        let code = match error.downcast_ref::<ActionError>() {
+            Some(ActionError::GameRunning) => ErrorCode::GameRunning,
+            Some(ActionError::Cancelled) => ErrorCode::Cancelled,
+            Some(ActionError::NoGameSelected) => ErrorCode::NoGameSelected,
+            Some(ActionError::NotFound(_)) => ErrorCode::NotFound,
+            Some(ActionError::Conflict(_)) => ErrorCode::Conflict,
+            Some(ActionError::InvalidInput(_)) => ErrorCode::BadRequest,
+            None => ErrorCode::Failed,
+        };
+        Self::new(code, format!("{:#}", error))
+    }
+}
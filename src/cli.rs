@@ -0,0 +1,75 @@
+use ytinu_core::{data::OpenUIConfig, install_link::InstallLink};
+
+static USAGE: &str = "Usage:\n\
+    ytinu [--ui chromium|browser|none]\n\
+    ytinu ytinu://install/<game>/<mod>[@<version>]\n\
+    ytinu --rollback\n\
+    \n\
+    Mods can be managed from the command line with ytinu-cli.";
+
+pub enum Command {
+    /// Start the server and open the UI
+    Gui(Option<OpenUIConfig>),
+    /// Start like [`Command::Gui`] and offer to install the mod of a `ytinu://` link
+    Install(InstallLink),
+    /// Restore the version of ytinu that was replaced by the last update
+    Rollback,
+}
+
+pub fn parse_args() -> Command {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [url] = args.as_slice() {
+        if url.to_lowercase().starts_with("ytinu:") {
+            return match url.parse() {
+                Ok(link) => Command::Install(link),
+                Err(error) => {
+                    crate::show_error(&format!("Invalid link '{}': {:#}", url, error));
+                    std::process::exit(-1);
+                }
+            };
+        }
+    }
+    match parse(&args) {
+        Some(command) => command,
+        None => {
+            crate::show_error(&format!("Invalid arguments. {}", USAGE));
+            std::process::exit(-1);
+        }
+    }
+}
+
+fn parse(args: &[String]) -> Option<Command> {
+    let mut args = args.iter().map(String::as_str);
+    let command = match args.next() {
+        None => Command::Gui(None),
+        Some("--ui") => {
+            let mode = match args.next()? {
+                "chromium" => OpenUIConfig::Chromium,
+                "browser" => OpenUIConfig::Browser,
+                "none" => OpenUIConfig::None,
+                _ => return None,
+            };
+            Command::Gui(Some(mode))
+        }
+        Some("--rollback") => Command::Rollback,
+        Some(_) => return None,
+    };
+    match args.next() {
+        None => Some(command),
+        Some(_) => None,
+    }
+}
+
+/// Runs a command-line action and returns the process exit code.
+pub fn run(command: Command) -> i32 {
+    match command {
+        Command::Gui(_) | Command::Install(_) => unreachable!("The UI is started by main"),
+        Command::Rollback => match crate::self_update::rollback() {
+            Ok(()) => 0,
+            Err(error) => {
+                crate::show_error(&format!("Failed to restore previous version: {:#}", error));
+                -1
+            }
+        },
+    }
+}
@@ -0,0 +1,121 @@
+//! Registers ytinu as the handler of `ytinu://` links, so websites can offer to install mods.
+//!
+//! ytinu is a single executable that can be anywhere, so every launch of a release build points
+//! the handler to the running executable. Debug builds leave the handler alone, so `cargo run`
+//! doesn't take over links from the installed version. On macOS the scheme can only be declared in
+//! the `Info.plist` of an app bundle, which ytinu doesn't have yet.
+
+#[cfg(any(target_os = "linux", windows))]
+pub fn register_url_handler() {
+    if cfg!(debug_assertions) {
+        return;
+    }
+    #[cfg(target_os = "linux")]
+    let result = register_desktop_entry();
+    #[cfg(windows)]
+    let result = register_url_protocol();
+    if let Err(error) = result {
+        log::warn!("Failed to register handler for ytinu:// links: {:#}", error);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn register_url_handler() {}
+
+/// Registers the scheme for the current user in `HKEY_CURRENT_USER\Software\Classes`
+#[cfg(windows)]
+fn register_url_protocol() -> anyhow::Result<()> {
+    use anyhow::Context;
+    use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+    use ytinu_core::install_link::SCHEME;
+
+    let exe = std::env::current_exe().context("Failed to get path of executable")?;
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    let user = RegKey::predef(HKEY_CURRENT_USER);
+    let path = format!(r"Software\Classes\{}", SCHEME);
+    let registered = user
+        .open_subkey(format!(r"{}\shell\open\command", path))
+        .and_then(|key| key.get_value::<String, _>(""));
+    if registered.ok().as_ref() == Some(&command) {
+        return Ok(());
+    }
+    let (key, _) = user
+        .create_subkey(&path)
+        .context("Failed to create registry key")?;
+    key.set_value("", &"URL:ytinu")?;
+    key.set_value("URL Protocol", &"")?;
+    let (open, _) = key.create_subkey(r"shell\open\command")?;
+    open.set_value("", &command)?;
+    log::info!("Registered handler for {}:// links", SCHEME);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn register_desktop_entry() -> anyhow::Result<()> {
+    use anyhow::Context;
+    use std::process::{Command, Stdio};
+    use ytinu_core::install_link::SCHEME;
+
+    static DESKTOP_FILE: &str = "ytinu.desktop";
+
+    let exe = std::env::current_exe().context("Failed to get path of executable")?;
+    let dir = app_dirs::data_root(app_dirs::AppDataType::UserData)
+        .context("Failed to get data directory")?
+        .join("applications");
+    let path = dir.join(DESKTOP_FILE);
+    // Quoting rules of the Exec key in the desktop entry specification
+    let exe = exe
+        .to_string_lossy()
+        .replace('\\', "\\\\\\\\")
+        .replace('"', "\\\\\"")
+        .replace('`', "\\\\`")
+        .replace('$', "\\\\$")
+        // Field codes like `%u` are expanded even inside quotes
+        .replace('%', "%%");
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=ytinu\n\
+         Comment=Mod Manager for Unity games\n\
+         Exec=\"{}\" %u\n\
+         Terminal=false\n\
+         NoDisplay=true\n\
+         MimeType=x-scheme-handler/{};\n",
+        exe, SCHEME
+    );
+    if std::fs::read_to_string(&path).ok().as_deref() == Some(entry.as_str()) {
+        return Ok(());
+    }
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+    ytinu_core::persist::write_atomic(&path, entry.as_bytes())?;
+    log::info!(
+        "Registered handler for {}:// links in '{}'",
+        SCHEME,
+        path.display()
+    );
+
+    // Both tools are optional. Desktops also pick up the entry on their next scan.
+    let mime_type = format!("x-scheme-handler/{}", SCHEME);
+    let commands: [&[&std::ffi::OsStr]; 2] = [
+        &[
+            "xdg-mime".as_ref(),
+            "default".as_ref(),
+            DESKTOP_FILE.as_ref(),
+            mime_type.as_ref(),
+        ],
+        &["update-desktop-database".as_ref(), dir.as_os_str()],
+    ];
+    for command in &commands {
+        let status = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        if let Err(error) = status {
+            log::warn!("Failed to run {}: {}", command[0].to_string_lossy(), error);
+        }
+    }
+    Ok(())
+}
@@ -6,29 +6,83 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use alcro::{dialog, Content, UIBuilder};
+use alcro::dialog::{self, MessageBoxIcon, YesNo};
+use anyhow::Context;
 use app_dirs::AppDataType;
-use data::OpenUIConfig;
 use server::ServerHandle;
 use simplelog::{
     CombinedLogger, Config, LevelFilter, SharedLogger, TermLogger, TerminalMode, WriteLogger,
 };
+use ytinu_core::{
+    data::{MessageIcon, OpenUIConfig},
+    dialog::Dialogs,
+    instance::{ActivateRequest, InstanceLock},
+};
 
+mod api;
 mod app;
-mod data;
+mod cli;
+mod desktop;
+#[cfg(test)]
+mod schema;
+mod self_update;
 mod server;
-mod utils;
+mod ui;
 
 pub use app::App;
-pub use utils::{show_error, ErrorExt};
-
-static APP_VERSION: semver::Version = semver::Version {
-    major: 0,
-    minor: 1,
-    patch: 0,
-    build: Vec::new(),
-    pre: Vec::new(),
-};
+pub use ytinu_core::{show_error, APP_VERSION};
+
+/// Debug builds only log messages and answer questions with their default answer if this
+/// environment variable is set, e.g. for end-to-end tests without a desktop.
+#[cfg(debug_assertions)]
+static NO_DIALOGS_ENV: &str = "YTINU_NO_DIALOGS";
+
+/// Lock of this process on the data directory. Released early to hand over to an update.
+static INSTANCE_LOCK: Mutex<Option<InstanceLock>> = Mutex::new(None);
+
+/// Shows the dialogs of the core library as native message boxes
+struct AlcroDialogs;
+
+impl Dialogs for AlcroDialogs {
+    fn message(&self, title: &str, message: &str, icon: MessageIcon) {
+        dialog::message_box_ok(title, message, message_box_icon(icon));
+    }
+
+    fn confirm(&self, title: &str, message: &str, icon: MessageIcon, default: bool) -> bool {
+        let default = if default { YesNo::Yes } else { YesNo::No };
+        dialog::message_box_yes_no(title, message, message_box_icon(icon), default) == YesNo::Yes
+    }
+}
+
+pub trait ErrorExt {
+    type R;
+    fn unwrap_or_die(self, title: &'static str) -> Self::R;
+}
+
+impl<T, E: std::fmt::Display> ErrorExt for Result<T, E> {
+    type R = T;
+    /// Shows the error to the user and quits
+    fn unwrap_or_die(self, title: &'static str) -> T {
+        self.unwrap_or_else(|error| {
+            log::error!("{}: {:#}", title, error);
+            ytinu_core::dialog::message(
+                title,
+                &format!("{}:\n\n{:#}", title, error),
+                MessageIcon::Error,
+            );
+            std::process::exit(-1);
+        })
+    }
+}
+
+fn message_box_icon(icon: MessageIcon) -> MessageBoxIcon {
+    match icon {
+        MessageIcon::Info => MessageBoxIcon::Info,
+        MessageIcon::Question => MessageBoxIcon::Question,
+        MessageIcon::Error => MessageBoxIcon::Error,
+        MessageIcon::Warning => MessageBoxIcon::Warning,
+    }
+}
 
 fn setup_panic_hook() {
     let default_panic_hook = std::panic::take_hook();
@@ -38,7 +92,7 @@ fn setup_panic_hook() {
         dialog::message_box_ok(
             "Unexpected Error",
             &format!("An unexpected error occured:\n\n{}", info),
-            dialog::MessageBoxIcon::Error,
+            MessageBoxIcon::Error,
         );
     }));
 }
@@ -50,7 +104,7 @@ fn setup_logging() {
         TerminalMode::Mixed,
     )];
     let mut error = None;
-    let log_file = match utils::app_dir(AppDataType::UserData) {
+    let log_file = match ytinu_core::utils::app_dir(AppDataType::UserData) {
         Ok(file) => file.join("ytinu.log"),
         Err(err) => {
             error = Some(err);
@@ -72,69 +126,105 @@ fn setup_logging() {
     }
 }
 
-fn launch_ui(app: Arc<Mutex<App>>, server_handle: ServerHandle, port: u16) {
+fn launch_ui(app: Arc<Mutex<App>>, server_handle: ServerHandle) {
     let ui_mode = app
         .lock()
         .unwrap_or_die("App::lock() failed")
         .config()
         .open_ui;
 
-    match ui_mode {
-        OpenUIConfig::Chromium => {
-            let ui = UIBuilder::new()
-                .content(Content::Url(&format!("http://127.0.0.1:{}/", port)))
-                .size(1200, 720)
-                .run()
-                .unwrap_or_die("Startup Error on UIBuilder::run");
-
-            ui.wait_finish();
-            log::info!("UI closed. Waiting for server to stop...");
-            server::stop();
-        }
-        OpenUIConfig::Browser => {
-            if let Err(error) = webbrowser::open(&format!("http://127.0.0.1:{}/", port)) {
-                crate::show_error(&format!("Failed to launch browser: {}", error));
-            }
-        }
-        OpenUIConfig::None => (),
+    ui::open(ui_mode, &server_handle.ui_url()).unwrap_or_die("Startup Error on ui::open");
+    if let OpenUIConfig::Chromium = ui_mode {
+        log::info!("UI closed. Waiting for server to stop...");
+        server::stop();
     }
 
     server_handle.join()
 }
 
+/// Locks the data directory. Exits if another instance is running, after passing on the UI
+/// request to it.
+fn lock_instance(command: &cli::Command) -> InstanceLock {
+    let result = match command {
+        cli::Command::Gui(ui_mode) => InstanceLock::acquire_or_hand_off(&ActivateRequest {
+            ui: *ui_mode,
+            install: None,
+        }),
+        cli::Command::Install(link) => InstanceLock::acquire_or_hand_off(&ActivateRequest {
+            ui: None,
+            install: Some(link.to_string()),
+        }),
+        cli::Command::Rollback => InstanceLock::acquire()
+            .and_then(|lock| {
+                lock.context(
+                    "ytinu is running. Please close it before restoring the previous version.",
+                )
+            })
+            .map(Some),
+    };
+    match result {
+        Ok(Some(lock)) => lock,
+        // The running instance shows its UI instead
+        Ok(None) => std::process::exit(0),
+        Err(error) => {
+            crate::show_error(&format!("{:#}", error));
+            std::process::exit(-1);
+        }
+    }
+}
+
 fn main() {
     setup_panic_hook();
+    #[cfg(debug_assertions)]
+    let dialogs = std::env::var_os(NO_DIALOGS_ENV).is_none();
+    #[cfg(not(debug_assertions))]
+    let dialogs = true;
+    if dialogs {
+        ytinu_core::dialog::set_handler(AlcroDialogs);
+    }
+    let command = cli::parse_args();
+    // Before setting up logging to not truncate the log file of a running instance
+    let lock = lock_instance(&command);
+    *INSTANCE_LOCK
+        .lock()
+        .unwrap_or_die("INSTANCE_LOCK::lock() failed") = Some(lock);
     setup_logging();
 
-    let ui_mode = parse_args();
+    let (ui_mode, install_link) = match command {
+        cli::Command::Gui(ui_mode) => (ui_mode, None),
+        cli::Command::Install(link) => (None, Some(link)),
+        command => std::process::exit(cli::run(command)),
+    };
 
+    self_update::check_pending();
+    desktop::register_url_handler();
     let app = App::start(ui_mode);
-    let (server_handle, port) = server::start(Arc::clone(&app));
-
-    log::info!("Started server on localhost:{}", port);
-
-    launch_ui(Arc::clone(&app), server_handle, port);
+    let server_handle = server::start(Arc::clone(&app));
+    if let Some(link) = install_link {
+        app.lock()
+            .unwrap_or_die("App::lock() failed")
+            .queue_install(link);
+    }
 
-    let app = app.lock();
-    if let Ok(app) = app {
-        app.remove_old_version();
+    log::info!("Started server on localhost:{}", server_handle.port());
+    if let Some(lock) = &*INSTANCE_LOCK
+        .lock()
+        .unwrap_or_die("INSTANCE_LOCK::lock() failed")
+    {
+        if let Err(error) = lock.publish(server_handle.port(), server_handle.token()) {
+            log::warn!("Later launches won't find this instance: {:#}", error);
+        }
     }
+    self_update::confirm();
+
+    launch_ui(app, server_handle);
+    self_update::wait_for_hand_over();
+    release_instance();
 }
 
-fn parse_args() -> Option<OpenUIConfig> {
-    let mut args_iter = std::env::args();
-    if args_iter.any(|a| a.as_str() == "--ui") {
-        let mode = match args_iter.next().as_deref() {
-            Some("chromium") => OpenUIConfig::Chromium,
-            Some("browser") => OpenUIConfig::Browser,
-            Some("none") => OpenUIConfig::None,
-            _ => {
-                crate::show_error("Invalid arguments. Usage: ytinu [--ui chromium|browser|none]");
-                std::process::exit(-1);
-            }
-        };
-        Some(mode)
-    } else {
-        None
+/// Unlocks the data directory so another ytinu process can take over
+pub fn release_instance() {
+    if let Ok(mut lock) = INSTANCE_LOCK.lock() {
+        lock.take();
     }
 }
@@ -0,0 +1,445 @@
+//! TypeScript definitions and OpenAPI description of the HTTP API, generated from the JSON
+//! schemas of the Rust types.
+//!
+//! The generated files are checked in (`svelte/src/data.d.ts` and `schema/openapi.json`) and a
+//! test fails if they are out of date. Run `YTINU_UPDATE_SCHEMA=1 cargo test` to update them.
+//!
+//! The server always sends all fields, so fields with a default value aren't optional in the
+//! TypeScript definitions. `Option`s are nullable instead.
+
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::{InstanceType, Schema, SchemaObject, SingleOrVec},
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+use ytinu_core::{
+    data::{Config, MetadataOut, StateOut, UpdateInfo},
+    dev_link::LinkRequest,
+    instance::ActivateRequest,
+    launch::LaunchOptions,
+    local_mod::LocalModRequest,
+    log_output::GameLog,
+    plugin_config::{ConfigEntry, ConfigEntryUpdate, PluginConfigInfo},
+    plugins::PluginReport,
+};
+
+use crate::api::ApiError;
+
+pub static TYPESCRIPT_PATH: &str = "svelte/src/data.d.ts";
+pub static OPENAPI_PATH: &str = "schema/openapi.json";
+/// Longer union types are split into one line per alternative
+static MAX_LINE_LENGTH: usize = 100;
+
+struct Endpoint {
+    method: &'static str,
+    /// Path relative to `/api/` with `{name}` placeholders
+    path: &'static str,
+    summary: &'static str,
+    query: &'static [&'static str],
+    body: Option<Schema>,
+    /// Status code and body of successful responses. `None` for `204 No Content`.
+    response: Option<(u16, Schema)>,
+}
+
+impl Endpoint {
+    fn new(method: &'static str, path: &'static str, summary: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            summary,
+            query: &[],
+            body: None,
+            response: None,
+        }
+    }
+
+    fn query(mut self, query: &'static [&'static str]) -> Self {
+        self.query = query;
+        self
+    }
+
+    fn body<T: JsonSchema>(mut self, gen: &mut SchemaGenerator) -> Self {
+        self.body = Some(gen.subschema_for::<T>());
+        self
+    }
+
+    fn returns<T: JsonSchema>(mut self, gen: &mut SchemaGenerator) -> Self {
+        self.response = Some((200, gen.subschema_for::<T>()));
+        self
+    }
+
+    fn created<T: JsonSchema>(mut self, gen: &mut SchemaGenerator) -> Self {
+        self.response = Some((201, gen.subschema_for::<T>()));
+        self
+    }
+}
+
+/// All API endpoints. Must match the routes in [`crate::api::Route`].
+fn endpoints(gen: &mut SchemaGenerator) -> Vec<Endpoint> {
+    vec![
+        Endpoint::new("GET", "state", "Setup games and app state").returns::<StateOut>(gen),
+        Endpoint::new("GET", "metadata", "Loaded metadata").returns::<Option<MetadataOut>>(gen),
+        Endpoint::new("GET", "config", "Settings").returns::<Config>(gen),
+        Endpoint::new("PUT", "config", "Change settings").body::<Config>(gen),
+        Endpoint::new("GET", "update", "Available ytinu update").returns::<UpdateInfo>(gen),
+        Endpoint::new("POST", "update", "Install the available update"),
+        Endpoint::new("PUT", "update/skipped", "Skip an update version")
+            .body::<Option<String>>(gen),
+        Endpoint::new("POST", "shutdown", "Stop the server"),
+        Endpoint::new(
+            "POST",
+            "activate",
+            "Show the UI for a later launch of ytinu",
+        )
+        .body::<ActivateRequest>(gen),
+        Endpoint::new(
+            "GET",
+            "dialogs/directory",
+            "Let the user select a directory",
+        )
+        .query(&["path"])
+        .returns::<Option<String>>(gen),
+        Endpoint::new("GET", "dialogs/file", "Let the user select a mod file")
+            .query(&["path"])
+            .returns::<Option<String>>(gen),
+        Endpoint::new(
+            "GET",
+            "games/detect",
+            "Detect the game installation directory",
+        )
+        .returns::<Option<String>>(gen),
+        Endpoint::new("POST", "games", "Set up a game from its install path").body::<String>(gen),
+        Endpoint::new("PUT", "game/install_path", "Change the install path").body::<String>(gen),
+        Endpoint::new("POST", "game/loader", "Install the mod loader"),
+        Endpoint::new(
+            "DELETE",
+            "game/loader",
+            "Remove the mod loader and all mods",
+        ),
+        Endpoint::new(
+            "PUT",
+            "game/loader/enabled",
+            "Enable or disable the mod loader",
+        )
+        .body::<bool>(gen),
+        Endpoint::new("POST", "game/launch", "Launch the game").body::<LaunchOptions>(gen),
+        Endpoint::new(
+            "POST",
+            "game/open/{dir}",
+            "Open the `game`, `mods` or `config` directory",
+        ),
+        Endpoint::new("GET", "game/plugins", "Installed plugins and problems")
+            .returns::<PluginReport>(gen),
+        Endpoint::new("GET", "game/log", "BepInEx log output")
+            .query(&["limit"])
+            .returns::<GameLog>(gen),
+        Endpoint::new("GET", "game/configs", "Plugin config files")
+            .returns::<Vec<PluginConfigInfo>>(gen),
+        Endpoint::new(
+            "GET",
+            "game/configs/{file}",
+            "Entries of a plugin config file",
+        )
+        .returns::<Vec<ConfigEntry>>(gen),
+        Endpoint::new(
+            "PATCH",
+            "game/configs/{file}",
+            "Change a plugin config entry",
+        )
+        .body::<ConfigEntryUpdate>(gen)
+        .returns::<Vec<ConfigEntry>>(gen),
+        Endpoint::new("POST", "game/mods", "Install a mod from a local file")
+            .body::<LocalModRequest>(gen),
+        Endpoint::new("PUT", "game/mods/{id}", "Install a mod from the metadata"),
+        Endpoint::new("DELETE", "game/mods/{id}", "Uninstall a mod"),
+        Endpoint::new("POST", "game/mods/{id}/update", "Update a mod"),
+        Endpoint::new(
+            "POST",
+            "game/mods/{id}/adopt",
+            "Manage a manually installed mod",
+        ),
+        Endpoint::new("POST", "game/links", "Link a local build directory")
+            .body::<LinkRequest>(gen)
+            .created::<String>(gen),
+        Endpoint::new("DELETE", "game/links/{id}", "Remove a linked mod"),
+    ]
+}
+
+fn generator() -> SchemaGenerator {
+    SchemaSettings::openapi3().into_generator()
+}
+
+/// Names and schemas of all types used by the API
+fn definitions() -> schemars::Map<String, Schema> {
+    let mut gen = generator();
+    endpoints(&mut gen);
+    gen.subschema_for::<ApiError>();
+    gen.take_definitions()
+}
+
+pub fn openapi() -> String {
+    let mut gen = generator();
+    let mut paths = Map::new();
+    for endpoint in endpoints(&mut gen) {
+        let mut parameters: Vec<Value> = endpoint
+            .path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+            .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}))
+            .collect();
+        parameters.extend(
+            endpoint
+                .query
+                .iter()
+                .map(|name| json!({"name": name, "in": "query", "schema": {"type": "string"}})),
+        );
+
+        let mut responses = Map::new();
+        match endpoint.response {
+            Some((status, schema)) => responses.insert(
+                status.to_string(),
+                json!({"description": "Success", "content": {"application/json": {"schema": schema}}}),
+            ),
+            None => responses.insert("204".to_string(), json!({"description": "Success"})),
+        };
+        responses.insert(
+            "default".to_string(),
+            json!({
+                "description": "Error",
+                "content": {"application/json": {"schema": gen.subschema_for::<ApiError>()}}
+            }),
+        );
+
+        let mut operation = json!({"summary": endpoint.summary, "responses": responses});
+        if !parameters.is_empty() {
+            operation["parameters"] = json!(parameters);
+        }
+        if let Some(body) = endpoint.body {
+            operation["requestBody"] =
+                json!({"required": true, "content": {"application/json": {"schema": body}}});
+        }
+        let path = format!("/api/{}", endpoint.path);
+        paths
+            .entry(path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .unwrap()
+            .insert(endpoint.method.to_lowercase(), operation);
+    }
+
+    let document = json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "ytinu",
+            "description": "HTTP API of the ytinu UI server. Generated from the Rust types.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": gen.take_definitions(),
+            "securitySchemes": {
+                "token": {"type": "apiKey", "in": "header", "name": crate::server::TOKEN_HEADER},
+            },
+        },
+        "security": [{"token": []}],
+    });
+    serde_json::to_string_pretty(&document).unwrap() + "\n"
+}
+
+pub fn typescript() -> String {
+    let mut out = String::from(
+        "// Generated from the Rust types by src/schema.rs. Do not edit.\n\
+         // Run `YTINU_UPDATE_SCHEMA=1 cargo test` to update.\n",
+    );
+    for (name, schema) in definitions() {
+        let schema = schema.into_object();
+        out.push('\n');
+        out.push_str(&doc_comment(&schema, ""));
+        match &schema.object {
+            Some(object) if !object.properties.is_empty() => {
+                out.push_str(&format!("interface {} {{\n", name));
+                for (field, field_schema) in &object.properties {
+                    if let Schema::Object(field_schema) = field_schema {
+                        out.push_str(&doc_comment(field_schema, "  "));
+                    }
+                    out.push_str(&format!("  {}: {};\n", field, ts_type(field_schema)));
+                }
+                out.push_str("}\n");
+            }
+            _ => {
+                let alternatives = ts_type(&schema.into());
+                let declaration = format!("type {} = {};\n", name, alternatives);
+                if declaration.len() <= MAX_LINE_LENGTH {
+                    out.push_str(&declaration);
+                } else {
+                    out.push_str(&format!("type {} =\n", name));
+                    for alternative in alternatives.split(" | ") {
+                        out.push_str(&format!("  | {}\n", alternative));
+                    }
+                    out.insert(out.len() - 1, ';');
+                }
+            }
+        }
+    }
+    out
+}
+
+fn doc_comment(schema: &SchemaObject, indent: &str) -> String {
+    let description = match schema
+        .metadata
+        .as_ref()
+        .and_then(|m| m.description.as_ref())
+    {
+        Some(description) => description,
+        None => return String::new(),
+    };
+    let lines: Vec<&str> = description.lines().collect();
+    if let [line] = lines[..] {
+        return format!("{}/** {} */\n", indent, line);
+    }
+    let mut comment = format!("{}/**\n", indent);
+    for line in lines {
+        comment.push_str(&format!("{} * {}\n", indent, line).replace(" * \n", " *\n"));
+    }
+    comment + &format!("{} */\n", indent)
+}
+
+fn ts_type(schema: &Schema) -> String {
+    let schema = match schema {
+        Schema::Bool(_) => return "unknown".to_string(),
+        Schema::Object(schema) => schema,
+    };
+    let nullable = schema.extensions.get("nullable") == Some(&Value::Bool(true));
+    let mut types = ts_types(schema);
+    if nullable && !types.iter().any(|t| t == "null") {
+        types.push("null".to_string());
+    }
+    types.join(" | ")
+}
+
+/// Alternatives of the union type matching a schema
+fn ts_types(schema: &SchemaObject) -> Vec<String> {
+    if let Some(reference) = &schema.reference {
+        return vec![reference.rsplit('/').next().unwrap().to_string()];
+    }
+    if let Some(values) = &schema.enum_values {
+        return values.iter().map(Value::to_string).collect();
+    }
+    if let Some(subschemas) = &schema.subschemas {
+        let alternatives = [&subschemas.all_of, &subschemas.any_of, &subschemas.one_of];
+        if let Some(schemas) = alternatives.iter().copied().flatten().next() {
+            return schemas.iter().map(ts_type).collect();
+        }
+    }
+    let instance_types = match &schema.instance_type {
+        Some(SingleOrVec::Single(instance_type)) => vec![**instance_type],
+        Some(SingleOrVec::Vec(instance_types)) => instance_types.clone(),
+        None => return vec!["unknown".to_string()],
+    };
+    instance_types
+        .into_iter()
+        .map(|instance_type| match instance_type {
+            InstanceType::Null => "null".to_string(),
+            InstanceType::Boolean => "boolean".to_string(),
+            InstanceType::Integer | InstanceType::Number => "number".to_string(),
+            InstanceType::String => "string".to_string(),
+            InstanceType::Array => {
+                let items = match schema.array.as_ref().and_then(|a| a.items.as_ref()) {
+                    Some(SingleOrVec::Single(items)) => ts_type(items),
+                    _ => "unknown".to_string(),
+                };
+                if items.contains(' ') {
+                    format!("({})[]", items)
+                } else {
+                    format!("{}[]", items)
+                }
+            }
+            InstanceType::Object => ts_object(schema),
+        })
+        .collect()
+}
+
+fn ts_object(schema: &SchemaObject) -> String {
+    let object = match &schema.object {
+        Some(object) => object,
+        None => return "{ [key: string]: unknown }".to_string(),
+    };
+    if object.properties.is_empty() {
+        let values = match &object.additional_properties {
+            Some(values) => ts_type(values),
+            None => "unknown".to_string(),
+        };
+        return format!("{{ [key: string]: {} }}", values);
+    }
+    let fields: Vec<String> = object
+        .properties
+        .iter()
+        .map(|(field, field_schema)| format!("{}: {}", field, ts_type(field_schema)))
+        .collect();
+    format!("{{ {} }}", fields.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, path::Path};
+
+    use super::*;
+    use crate::api::Route;
+
+    /// Compares a generated file with the checked in version or updates it if
+    /// `YTINU_UPDATE_SCHEMA` is set.
+    fn check_generated(path: &str, generated: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+        if std::env::var_os("YTINU_UPDATE_SCHEMA").is_some() {
+            std::fs::write(&path, generated).unwrap();
+            return;
+        }
+        let current = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            current.replace("\r\n", "\n") == generated,
+            "'{}' is out of date. Run `YTINU_UPDATE_SCHEMA=1 cargo test` to update it.",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn typescript_is_up_to_date() {
+        check_generated(TYPESCRIPT_PATH, &typescript());
+    }
+
+    #[test]
+    fn openapi_is_up_to_date() {
+        check_generated(OPENAPI_PATH, &openapi());
+    }
+
+    #[test]
+    fn endpoints_match_routes() {
+        let endpoints = endpoints(&mut generator());
+        let mut routes = HashSet::new();
+        for endpoint in &endpoints {
+            let path = endpoint
+                .path
+                .split('/')
+                .map(|segment| {
+                    if segment.starts_with('{') {
+                        "x"
+                    } else {
+                        segment
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            let route = Route::parse(endpoint.method, &path)
+                .unwrap_or_else(|e| panic!("{} {}: {}", endpoint.method, endpoint.path, e));
+            let variant = format!("{:?}", route);
+            routes.insert(variant.split('(').next().unwrap().to_string());
+        }
+        assert_eq!(
+            routes.len(),
+            endpoints.len(),
+            "Endpoints with the same route"
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Self-update of the ytinu executable.
+//!
+//! Updates are staged: the new binary is downloaded next to the executable, checked against the
+//! release checksum and signature and only then swapped in. The previous binary is kept as
+//! `ytinu_old`. The previous process waits for the updated one to confirm a successful start and
+//! restores the previous binary if it doesn't. Should that fail as well, the previous binary is
+//! restored on the next launch. `ytinu --rollback` restores it manually.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use anyhow::{ensure, Context};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use ytinu_core::{
+    data::{AppRelease, MessageIcon},
+    fs::RealFs,
+    Env, APP_VERSION,
+};
+
+static NEW_FILE: &str = "ytinu_new";
+static OLD_FILE: &str = "ytinu_old";
+/// Binary replaced by a rollback. It can't be removed on Windows while it is still running.
+static FAILED_FILE: &str = "ytinu_failed";
+static PENDING_FILE: &str = "ytinu_update.json";
+/// How long the previous process waits for the updated one to confirm its start. It fetches the
+/// metadata and may show dialogs before.
+static CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+/// Set once this process started handing over to a new one. It exits when that is done.
+static HANDING_OVER: AtomicBool = AtomicBool::new(false);
+
+/// Update that was installed but whose first start wasn't confirmed yet
+#[derive(Serialize, Deserialize, Debug)]
+struct PendingUpdate {
+    from: Version,
+    to: Version,
+    /// Number of starts of the new version
+    starts: u32,
+}
+
+/// Location of the running executable and its directory
+fn exe_paths() -> anyhow::Result<(PathBuf, PathBuf)> {
+    let exe_path =
+        std::env::current_exe().context("Failed to get location of ytinu installation")?;
+    let exe_dir = exe_path
+        .parent()
+        .context("Failed to get directory of ytinu installation")?
+        .to_path_buf();
+    Ok((exe_path, exe_dir))
+}
+
+/// Downloads, verifies and installs a release and restarts ytinu. Only returns on errors before
+/// the new version was started.
+pub fn install(env: &Env, release: &AppRelease) -> anyhow::Result<()> {
+    let os = std::env::consts::OS;
+    let url = release
+        .downloads
+        .get(os)
+        .with_context(|| format!("No update url for OS: '{}'", os))?;
+    // Without a checksum only the signature would be checked, which is a metadata mistake
+    let hash = release
+        .hashes
+        .get(os)
+        .with_context(|| format!("No update checksum for OS: '{}'", os))?;
+    let (exe_path, exe_dir) = exe_paths()?;
+
+    let new_path = exe_dir.join(NEW_FILE);
+    ytinu_core::utils::download(env, url, &new_path)?;
+    if let Err(error) = verify(env, &new_path, hash, url) {
+        let _ = std::fs::remove_file(&new_path);
+        return Err(error.context("The update was rejected"));
+    }
+    make_executable(&new_path)?;
+
+    let pending = PendingUpdate {
+        from: APP_VERSION.clone(),
+        to: release.version.clone(),
+        starts: 0,
+    };
+    write_pending(&exe_dir, &pending)?;
+
+    let old_path = exe_dir.join(OLD_FILE);
+    if let Err(error) = swap(&exe_path, &new_path, &old_path) {
+        let _ = std::fs::remove_file(&new_path);
+        let _ = std::fs::remove_file(exe_dir.join(PENDING_FILE));
+        return Err(error);
+    }
+
+    log::info!(
+        "Successfully updated to {}. Previous version is kept at '{}'. Restarting...",
+        release.version,
+        old_path.to_string_lossy()
+    );
+    let confirmed = match hand_over(&exe_path) {
+        Ok(mut child) => wait_for_confirmation(&exe_dir, &mut child),
+        Err(error) => {
+            log::error!("Failed to start new process: {}", error);
+            false
+        }
+    };
+    if confirmed {
+        log::info!("ytinu {} started successfully", release.version);
+        std::process::exit(0);
+    }
+    restore(&exe_path, &exe_dir, &pending);
+    std::process::exit(-1);
+}
+
+/// Waits until the updated process confirmed its start. Stops it if that takes too long.
+fn wait_for_confirmation(exe_dir: &Path, child: &mut Child) -> bool {
+    let pending_path = exe_dir.join(PENDING_FILE);
+    let start = Instant::now();
+    loop {
+        let exited = match child.try_wait() {
+            Ok(status) => status,
+            Err(error) => {
+                log::warn!("Failed to check the new process: {}", error);
+                None
+            }
+        };
+        // Checked after the exit so a process that confirmed and then exited counts as confirmed
+        if !pending_path.exists() {
+            return true;
+        }
+        if let Some(status) = exited {
+            log::error!(
+                "The new version exited without confirming its start: {}",
+                status
+            );
+            return false;
+        }
+        if start.elapsed() > CONFIRM_TIMEOUT {
+            log::error!(
+                "The new version didn't confirm its start within {} seconds. Stopping it.",
+                CONFIRM_TIMEOUT.as_secs()
+            );
+            let _ = child.kill();
+            let _ = child.wait();
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(250));
+    }
+}
+
+fn verify(env: &Env, path: &Path, hash: &str, url: &str) -> anyhow::Result<()> {
+    let actual =
+        ytinu_core::utils::checksum(&RealFs, path).context("Failed to read downloaded update")?;
+    ensure!(
+        actual == hash,
+        "Checksum mismatch. Expected {} but got {}",
+        hash,
+        actual
+    );
+    let data = std::fs::read(path).context("Failed to read downloaded update")?;
+    ytinu_core::signature::verify_download(&*env.fetcher, &data, url)
+}
+
+/// Replaces `exe_path` with `new_path` and moves the current executable to `old_path`.
+fn swap(exe_path: &Path, new_path: &Path, old_path: &Path) -> anyhow::Result<()> {
+    std::fs::rename(exe_path, old_path).context("Failed to move current version")?;
+    if let Err(error) = std::fs::rename(new_path, exe_path) {
+        log::error!(
+            "Failed to move new version into place: {}. Restoring current version.",
+            error
+        );
+        std::fs::rename(old_path, exe_path).with_context(|| {
+            format!(
+                "Failed to move new version into place: {}\n\n\
+                 Also failed to restore the current version which is located at '{}'",
+                error,
+                old_path.to_string_lossy()
+            )
+        })?;
+        return Err(error).context("Failed to move new version into place");
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+        .context("Failed to make the new version executable")
+}
+
+#[cfg(windows)]
+fn make_executable(_path: &Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Stops the server and releases the data directory, then starts `exe_path` in a new process
+/// that takes over both.
+fn hand_over(exe_path: &Path) -> std::io::Result<Child> {
+    HANDING_OVER.store(true, Ordering::SeqCst);
+    crate::server::stop();
+    crate::release_instance();
+    Command::new(exe_path)
+        .args(std::env::args().skip(1))
+        .spawn()
+}
+
+/// Keeps the process alive while it hands over to a new one, even though the server stopped
+pub fn wait_for_hand_over() {
+    while HANDING_OVER.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_millis(250));
+    }
+}
+
+fn restart(exe_path: &Path) -> ! {
+    if let Err(error) = hand_over(exe_path) {
+        log::warn!("Failed to start new process: {}", error);
+        ytinu_core::dialog::message(
+            "Failed to restart",
+            "ytinu was not able to restart itself automatically. Please start it again manually.",
+            MessageIcon::Info,
+        );
+    }
+    std::process::exit(0);
+}
+
+/// Restores the version that was replaced by the `pending` update and starts it. Only returns if
+/// restoring failed.
+fn restore(exe_path: &Path, exe_dir: &Path, pending: &PendingUpdate) {
+    log::error!(
+        "ytinu {} didn't start successfully after the update. Restoring {}.",
+        pending.to,
+        pending.from
+    );
+    match rollback_at(exe_path, exe_dir) {
+        Ok(()) => {
+            crate::show_error(&format!(
+                "ytinu {} failed to start after the update. The previous version {} was restored.",
+                pending.to, pending.from
+            ));
+            restart(exe_path);
+        }
+        Err(error) => crate::show_error(&format!(
+            "ytinu {} failed to start after the update and restoring the previous version {} failed:\n\n{:#}",
+            pending.to, pending.from, error
+        )),
+    }
+}
+
+fn read_pending(exe_dir: &Path) -> Option<PendingUpdate> {
+    let file = std::fs::File::open(exe_dir.join(PENDING_FILE)).ok()?;
+    serde_json::from_reader(file)
+        .map_err(|e| log::warn!("Failed to parse pending update: {}", e))
+        .ok()
+}
+
+fn write_pending(exe_dir: &Path, pending: &PendingUpdate) -> anyhow::Result<()> {
+    let json = serde_json::to_string(pending)?;
+    std::fs::write(exe_dir.join(PENDING_FILE), json).context("Failed to store pending update")
+}
+
+/// Called on startup. Restores the previous version if an update failed to start before.
+pub fn check_pending() {
+    let (exe_path, exe_dir) = match exe_paths() {
+        Ok(paths) => paths,
+        Err(error) => return log::warn!("Failed to check for pending update: {:#}", error),
+    };
+    let mut pending = match read_pending(&exe_dir) {
+        Some(pending) => pending,
+        None => return,
+    };
+    if pending.to != APP_VERSION {
+        log::warn!(
+            "Pending update to {} doesn't match the running version {}. Ignoring it.",
+            pending.to,
+            APP_VERSION
+        );
+        let _ = std::fs::remove_file(exe_dir.join(PENDING_FILE));
+        return;
+    }
+    if pending.starts == 0 {
+        pending.starts += 1;
+        if let Err(error) = write_pending(&exe_dir, &pending) {
+            log::warn!("{:#}", error);
+        }
+        return;
+    }
+
+    restore(&exe_path, &exe_dir, &pending);
+}
+
+/// Marks a pending update as successfully started.
+pub fn confirm() {
+    let exe_dir = match exe_paths() {
+        Ok((_, exe_dir)) => exe_dir,
+        Err(error) => return log::warn!("Failed to confirm update: {:#}", error),
+    };
+    if let Some(pending) = read_pending(&exe_dir) {
+        log::info!("Update from {} to {} successful", pending.from, pending.to);
+        if let Err(error) = std::fs::remove_file(exe_dir.join(PENDING_FILE)) {
+            log::warn!("Failed to remove pending update: {}", error);
+        }
+    }
+    let failed = exe_dir.join(FAILED_FILE);
+    if failed.is_file() {
+        log::info!(
+            "Removing leftover executable at '{}'",
+            failed.to_string_lossy()
+        );
+        if let Err(error) = std::fs::remove_file(&failed) {
+            log::warn!("Failed to remove leftover executable: {}", error);
+        }
+    }
+}
+
+/// Restores the version that was replaced by the last update.
+pub fn rollback() -> anyhow::Result<()> {
+    let (exe_path, exe_dir) = exe_paths()?;
+    rollback_at(&exe_path, &exe_dir)
+}
+
+/// Restores the previous version at `exe_path`. Needed by the process that installed the update
+/// because its own executable has been moved to `ytinu_old` by then.
+fn rollback_at(exe_path: &Path, exe_dir: &Path) -> anyhow::Result<()> {
+    let old_path = exe_dir.join(OLD_FILE);
+    ensure!(
+        old_path.is_file(),
+        "No previous version found at '{}'",
+        old_path.to_string_lossy()
+    );
+    let failed_path = exe_dir.join(FAILED_FILE);
+    swap(exe_path, &old_path, &failed_path)?;
+    let _ = std::fs::remove_file(exe_dir.join(PENDING_FILE));
+    if std::fs::remove_file(&failed_path).is_err() {
+        log::info!(
+            "Replaced version is kept at '{}' until the next start",
+            failed_path.to_string_lossy()
+        );
+    }
+    log::info!(
+        "Restored previous version from '{}'",
+        old_path.to_string_lossy()
+    );
+    Ok(())
+}
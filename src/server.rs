@@ -1,41 +1,66 @@
-use rouille::{Response, Server};
+use rouille::{Request, Response, Server};
 use rust_embed::RustEmbed;
-use serde::Serialize;
 use std::{
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU16, Ordering},
         Arc, Mutex,
     },
     thread::JoinHandle,
 };
 
-use crate::{app::App, ErrorExt};
+use crate::{
+    api::{ApiError, ErrorCode, Route},
+    app::App,
+    ErrorExt,
+};
+
+pub use ytinu_core::instance::TOKEN_HEADER;
 
 static STOP_SERVER: AtomicBool = AtomicBool::new(false);
+static SERVER_PORT: AtomicU16 = AtomicU16::new(0);
+
+/// Origin of the development server of the UI
+#[cfg(debug_assertions)]
+static DEV_ORIGIN: &str = "http://localhost:5000";
 
 #[derive(RustEmbed)]
 #[folder = "svelte/public"]
 struct Asset;
 
-fn run(app: Arc<Mutex<App>>, port_tx: std::sync::mpsc::Sender<u16>) {
-    let port = if cfg!(debug_assertions) {
-        5001
-    } else {
-        app.lock().unwrap_or_die("App::lock() failed").config().port
+fn run(app: Arc<Mutex<App>>, token: String, port_tx: std::sync::mpsc::Sender<u16>) {
+    // Debug builds default to the port the development server of the UI connects to
+    let port = match app.lock().unwrap_or_die("App::lock() failed").config().port {
+        0 if cfg!(debug_assertions) => 5001,
+        port => port,
     };
     let server = Server::new(("127.0.0.1", port), move |request| {
         let path = request.url();
 
-        log::info!("Request: {}", path);
+        log::info!("Request: {} {}", request.method(), path);
+
+        if let Err(error) = check_host(request) {
+            log::warn!("Rejected request: {}", error);
+            return Response::text(error).with_status_code(403);
+        }
 
         let path = if path == "/" {
             "index.html"
         } else if let Some(path) = path.strip_prefix("/api/") {
-            let mut app = app.lock().unwrap_or_die("App::lock() failed");
-            match app.handle(path, request) {
-                Ok(response) => return add_cors(response),
-                Err(error) => return add_cors(Response::json(&APIErrorResponse::new(error))),
+            if cfg!(debug_assertions) && request.method() == "OPTIONS" {
+                return add_cors(Response::empty_204());
+            }
+            if let Err(error) = authorize(request, &token) {
+                log::warn!("Rejected request: {}", error);
+                return add_cors(error.response());
             }
+            let result = Route::parse(request.method(), path).and_then(|route| {
+                let mut app = app.lock().unwrap_or_die("App::lock() failed");
+                app.handle(route, request)
+            });
+            return add_cors(result.unwrap_or_else(|error| {
+                log::warn!("API error: {}", error);
+                error.response()
+            }));
         } else {
             &path[1..]
         };
@@ -53,6 +78,7 @@ fn run(app: Arc<Mutex<App>>, port_tx: std::sync::mpsc::Sender<u16>) {
     .unwrap_or_die("Startup Error on Server::new()");
 
     let port = server.server_addr().port();
+    SERVER_PORT.store(port, Ordering::SeqCst);
     port_tx
         .send(port)
         .unwrap_or_die("Startup Error on port::send()");
@@ -64,9 +90,71 @@ fn run(app: Arc<Mutex<App>>, port_tx: std::sync::mpsc::Sender<u16>) {
     log::info!("Server stopped.");
 }
 
+/// Rejects requests for other host names to prevent DNS rebinding attacks.
+fn check_host(request: &Request) -> Result<(), String> {
+    let port = SERVER_PORT.load(Ordering::SeqCst);
+    let host = request.header("Host").unwrap_or_default();
+    if host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port) {
+        Ok(())
+    } else {
+        Err(format!("Invalid host: '{}'", host))
+    }
+}
+
+/// Checks the origin and session token of an API request.
+fn authorize(request: &Request, token: &str) -> Result<(), ApiError> {
+    if let Some(origin) = request.header("Origin") {
+        let port = SERVER_PORT.load(Ordering::SeqCst);
+        let allowed = origin == format!("http://127.0.0.1:{}", port)
+            || origin == format!("http://localhost:{}", port)
+            || (cfg!(debug_assertions) && is_dev_origin(origin));
+        if !allowed {
+            return Err(ApiError::new(
+                ErrorCode::Forbidden,
+                format!("Invalid origin: '{}'", origin),
+            ));
+        }
+    }
+    let request_token = request.header(TOKEN_HEADER).unwrap_or_default();
+    if !constant_time_eq(request_token.as_bytes(), token.as_bytes()) {
+        return Err(ApiError::new(
+            ErrorCode::Forbidden,
+            "Missing or invalid session token",
+        ));
+    }
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Random token that authorizes API requests for the lifetime of the server
+fn session_token() -> String {
+    let mut bytes = [0; 32];
+    getrandom::getrandom(&mut bytes).unwrap_or_die("Startup Error on getrandom()");
+    ytinu_core::utils::to_hex(&bytes)
+}
+
+#[cfg(debug_assertions)]
+fn is_dev_origin(origin: &str) -> bool {
+    origin == DEV_ORIGIN
+}
+
+#[cfg(not(debug_assertions))]
+fn is_dev_origin(_origin: &str) -> bool {
+    false
+}
+
 #[cfg(debug_assertions)]
 fn add_cors(response: Response) -> Response {
-    response.with_additional_header("Access-Control-Allow-Origin", "http://localhost:5000")
+    response
+        .with_additional_header("Access-Control-Allow-Origin", DEV_ORIGIN)
+        .with_additional_header(
+            "Access-Control-Allow-Methods",
+            "GET, POST, PUT, PATCH, DELETE",
+        )
+        .with_additional_header("Access-Control-Allow-Headers", TOKEN_HEADER)
 }
 
 #[cfg(not(debug_assertions))]
@@ -74,15 +162,27 @@ fn add_cors(response: Response) -> Response {
     response
 }
 
-pub struct ServerHandle(JoinHandle<()>);
+pub struct ServerHandle {
+    thread: JoinHandle<()>,
+    port: u16,
+    token: String,
+}
 
-pub fn start(app: Arc<Mutex<App>>) -> (ServerHandle, u16) {
+pub fn start(app: Arc<Mutex<App>>) -> ServerHandle {
+    let token = session_token();
     let (port_tx, port_rx) = std::sync::mpsc::channel();
-    let handle = std::thread::spawn(move || run(app, port_tx));
+    let thread = {
+        let token = token.clone();
+        std::thread::spawn(move || run(app, token, port_tx))
+    };
     let port = port_rx
         .recv()
         .unwrap_or_die("Startup Error on port::recv()");
-    (ServerHandle(handle), port)
+    ServerHandle {
+        thread,
+        port,
+        token,
+    }
 }
 
 pub fn stop() {
@@ -90,20 +190,22 @@ pub fn stop() {
 }
 
 impl ServerHandle {
-    pub fn join(self: ServerHandle) {
-        if let Err(error) = self.0.join() {
-            log::error!("Error while joining server thread: {:?}", error);
-        }
+    pub fn port(&self) -> u16 {
+        self.port
     }
-}
 
-#[derive(Serialize)]
-struct APIErrorResponse {
-    error: String,
-}
+    pub fn token(&self) -> &str {
+        &self.token
+    }
 
-impl APIErrorResponse {
-    fn new(error: String) -> Self {
-        Self { error }
+    /// URL of the UI including the session token
+    pub fn ui_url(&self) -> String {
+        format!("http://127.0.0.1:{}/?token={}", self.port, self.token)
+    }
+
+    pub fn join(self: ServerHandle) {
+        if let Err(error) = self.thread.join() {
+            log::error!("Error while joining server thread: {:?}", error);
+        }
     }
 }
@@ -0,0 +1,59 @@
+//! Opening the UI in a Chromium window or the browser, also when a later launch of ytinu asks the
+//! running instance to show it.
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use alcro::{Content, UIBuilder, WindowState, UI};
+use anyhow::anyhow;
+use ytinu_core::data::OpenUIConfig;
+
+/// URL of the UI including the session token
+static URL: OnceLock<String> = OnceLock::new();
+/// The open Chromium window, if any
+static WINDOW: Mutex<Option<Arc<UI>>> = Mutex::new(None);
+
+/// Opens the UI. For [`OpenUIConfig::Chromium`] only returns after the window was closed.
+pub fn open(mode: OpenUIConfig, url: &str) -> anyhow::Result<()> {
+    let _ = URL.set(url.to_string());
+    match mode {
+        OpenUIConfig::Chromium => {
+            let window = UIBuilder::new()
+                .content(Content::Url(url))
+                .size(1200, 720)
+                .run()
+                .map_err(|error| anyhow!("Failed to open window: {}", error))?;
+            let window = Arc::new(window);
+            *WINDOW.lock().unwrap() = Some(Arc::clone(&window));
+            window.wait_finish();
+            *WINDOW.lock().unwrap() = None;
+        }
+        OpenUIConfig::Browser => {
+            webbrowser::open(url)
+                .map_err(|error| anyhow!("Failed to launch browser: {}", error))?;
+        }
+        OpenUIConfig::None => log::info!("Open the UI at {}", url),
+    }
+    Ok(())
+}
+
+/// Shows the UI for a later launch of ytinu. Brings an open window to the front or opens the UI
+/// again.
+pub fn activate(mode: OpenUIConfig) {
+    let window = WINDOW.lock().unwrap().clone();
+    if let Some(window) = window {
+        // Restores a minimized window. Chromium doesn't allow raising windows otherwise.
+        let _ = window.set_bounds(WindowState::Normal.to_bounds());
+        let _ = window.eval("window.focus()");
+        return;
+    }
+    let url = match URL.get() {
+        Some(url) => url.clone(),
+        None => return,
+    };
+    // The server keeps running when this window is closed, unlike the initial window
+    std::thread::spawn(move || {
+        if let Err(error) = open(mode, &url) {
+            crate::show_error(&format!("{:#}", error));
+        }
+    });
+}
@@ -1,2 +1,32 @@
 export const HOST = IS_PRODUCTION ? "" : "http://localhost:5001";
 export const API_BASE = HOST + "/api/";
+
+const TOKEN_KEY = "ytinu-token";
+
+// The session token is passed in the URL when ytinu opens the UI
+const params = new URLSearchParams(window.location.search);
+const token = params.get("token") ?? sessionStorage.getItem(TOKEN_KEY) ?? "";
+if (params.has("token")) {
+  sessionStorage.setItem(TOKEN_KEY, token);
+  window.history.replaceState(null, "", window.location.pathname);
+}
+
+export function api(endpoint: string, init: RequestInit = {}): Promise<Response> {
+  return fetch(API_BASE + endpoint, {
+    ...init,
+    headers: { "X-Ytinu-Token": token },
+  });
+}
+
+/// Sends a request that changes state. Resolves to the parsed response body or `null` for
+/// empty responses. Failed requests resolve to an `ApiError`.
+export function send(
+  method: "POST" | "PUT" | "PATCH" | "DELETE",
+  endpoint: string,
+  body?: any
+): Promise<any> {
+  return api(endpoint, {
+    method,
+    body: body === undefined ? undefined : JSON.stringify(body),
+  }).then((r) => (r.status === 204 ? null : r.json()));
+}
@@ -1,32 +1,43 @@
+// Generated from the Rust types by src/schema.rs. Do not edit.
+// Run `YTINU_UPDATE_SCHEMA=1 cargo test` to update.
+
 interface State {
   version: string;
-  selected_game: string;
-  games: { [id: string]: SetupGame };
+  selected_game: string | null;
+  games: { [key: string]: SetupGame };
   os: string;
-}
-
-interface Game {
-  id: string;
-  name: string;
-  recommended_mods: string[];
+  running_games: string[];
+  /** Plugin files of the selected game that aren't managed by ytinu */
+  unmanaged: UnmanagedFile[];
 }
 
 interface SetupGame {
   game: Game;
   install_path: string;
-  mods: { [id: string]: InstalledMod };
-  bep_in_ex: BepInExInfo;
+  mods: { [key: string]: InstalledMod };
+  bep_in_ex: BepInExInfo | null;
+  launches: LaunchRecord[];
+  /** Manually installed mods the user doesn't want ytinu to manage */
+  declined_adoptions: string[];
+  /** Mods deployed from a local build directory */
+  linked_mods: { [key: string]: LinkedMod };
 }
 
-interface BepInExInfo {
-  version?: string;
-  enabled: boolean;
+interface Game {
+  id: string;
+  name: string;
+  appid: string | null;
+  /** Path of the game executable relative to the installation directory */
+  executable: string | null;
+  recommended_mods: string[];
 }
 
 interface InstalledMod {
   m: Mod;
   version: string;
   enabled: boolean;
+  /** Files that were actually installed, relative to the install path. Only set if they differ from `m.files`, e.g. for adopted mods. */
+  files: string[] | null;
 }
 
 interface Mod {
@@ -34,24 +45,250 @@ interface Mod {
   name: string;
   download: string;
   version: string;
-  source?: string;
-  homepage?: string;
-  description?: string;
-  ytinu_version?: string;
-  dev_mod?: boolean;
+  extract_to_root: boolean;
+  files: string[] | null;
+  /** blake2s checksum of the downloaded file */
+  hash: string | null;
+  /** Size of the downloaded file in bytes */
+  size: number | null;
+  /** Installed from a local file instead of a metadata repository */
+  local: boolean;
+  dev_mod: boolean;
+  source: string | null;
+  homepage: string | null;
+  description: string | null;
+  ytinu_version: string | null;
+}
+
+interface BepInExInfo {
+  version: string | null;
+  enabled: boolean;
+  hash: string | null;
+}
+
+interface LaunchRecord {
+  /** Unix timestamp (in seconds) of the launch. */
+  started: number;
+  /** Runtime in seconds. Only known for direct launches that have finished. */
+  duration: number | null;
+  vanilla: boolean;
+  steam: boolean;
+  exit_code: number | null;
+}
+
+interface LinkedMod {
+  /** Build output directory of the mod */
+  source: string;
+  mode: LinkMode;
+  /** Deploy to `BepInEx/scripts` instead of `BepInEx/plugins` */
+  scripts: boolean;
+  /** Deployed files and directories, relative to the install path */
+  deployed: string[];
+}
+
+type LinkMode = "symlink" | "copy";
+
+interface UnmanagedFile {
+  /** Path relative to the plugins directory */
+  path: string;
+  /** Known mod this file belongs to */
+  matched_mod: string | null;
+  /** `true` if the file's checksum matches the download of the mod exactly */
+  exact: boolean;
+  /** Version read from the plugin metadata */
+  version: string | null;
 }
 
 interface Metadata {
-  version: string;
+  format: number;
+  /** Latest ytinu version on the configured update channel */
+  version: string | null;
+  /** Whether the latest version is newer than the running one and not skipped */
   update: boolean;
-  games: { [id: string]: Game };
-  game_mods: { [id: string]: { [id: string]: Mod } };
-  mods: { [id: string]: Mod };
+  releases: { [key: string]: AppRelease };
+  games: { [key: string]: Game };
+  game_mods: { [key: string]: { [key: string]: Mod } };
+  mods: { [key: string]: Mod };
+  warnings: string[];
+}
+
+/** ytinu release of an update channel */
+interface AppRelease {
+  version: string;
+  /** Download URL by OS (`windows`, `linux`, `macos`) */
+  downloads: { [key: string]: string };
+  /** blake2s checksum of the download by OS */
+  hashes: { [key: string]: string };
+  notes: string | null;
 }
 
 interface Config {
-  dark_mode: 'system' | 'dark' | 'light';
+  dark_mode: DarkMode;
   show_dev_mods: boolean;
+  port: number;
   check_for_updates: boolean;
-  open_ui: 'chromium' | 'browser' | 'none';
+  update_channel: UpdateChannel;
+  /** Release the user doesn't want to be notified about */
+  skipped_version: string | null;
+  open_ui: OpenUIConfig;
+}
+
+type DarkMode = "system" | "dark" | "light";
+
+type UpdateChannel = "stable" | "beta";
+
+type OpenUIConfig = "chromium" | "browser" | "none";
+
+/** Available ytinu update for the configured channel */
+interface UpdateInfo {
+  installed: string;
+  channel: UpdateChannel;
+  release: AppRelease | null;
+  /** The user chose to skip this release */
+  skipped: boolean;
+  /** Release notes of all versions between the installed and the available release */
+  notes: ReleaseNotes[];
+}
+
+interface ReleaseNotes {
+  version: string;
+  notes: string;
 }
+
+/** Sent by a later launch to the running instance */
+interface ActivateRequest {
+  /** UI requested on the command line. The configured UI is used if `null`. */
+  ui: OpenUIConfig | null;
+  /** `ytinu://` link of a mod to install after asking the user */
+  install: string | null;
+}
+
+interface LaunchOptions {
+  /** Start the game without loading BepInEx for this run only. */
+  vanilla: boolean;
+  /** Start the game through Steam instead of running the executable directly. */
+  steam: boolean;
+}
+
+interface PluginReport {
+  files: PluginFile[];
+  problems: PluginProblem[];
+}
+
+interface PluginFile {
+  /** Path relative to the plugins directory */
+  path: string;
+  assembly: AssemblyInfo | null;
+  /** Set if the file is not a readable .NET assembly */
+  error: string | null;
+  /** Installed mod the file belongs to */
+  mod_id: string | null;
+  /** Known mod that an unmanaged file most likely belongs to */
+  matched_mod: string | null;
+}
+
+interface AssemblyInfo {
+  name: string | null;
+  version: string | null;
+  plugins: PluginInfo[];
+}
+
+interface PluginInfo {
+  /** Full name of the class the attributes are declared on */
+  type_name: string;
+  guid: string;
+  name: string;
+  version: string;
+  dependencies: PluginDependency[];
+  processes: string[];
+}
+
+interface PluginDependency {
+  guid: string;
+  soft: boolean;
+  min_version: string | null;
+}
+
+interface PluginProblem {
+  guid: string;
+  file: string;
+  message: string;
+}
+
+interface GameLog {
+  entries: LogEntry[];
+  /** Number of exceptions per attributed mod id */
+  exceptions: { [key: string]: number };
+  /** Number of exceptions that couldn't be attributed to any installed mod */
+  unattributed_exceptions: number;
+}
+
+interface LogEntry {
+  level: string;
+  source: string;
+  message: string;
+  exception: boolean;
+  /** Id of the installed mod this entry was attributed to */
+  mod_id: string | null;
+}
+
+interface PluginConfigInfo {
+  file: string;
+  plugin: string | null;
+  guid: string | null;
+}
+
+interface ConfigEntry {
+  section: string;
+  key: string;
+  value: string;
+  description: string | null;
+  setting_type: string | null;
+  default_value: string | null;
+  acceptable: AcceptableValues | null;
+  /** Whether multiple values from `acceptable` can be combined with `,` (flag enums) */
+  multiple: boolean;
+}
+
+type AcceptableValues =
+  | { kind: "list"; values: string[] }
+  | { kind: "range"; min: string; max: string };
+
+interface ConfigEntryUpdate {
+  section: string;
+  key: string;
+  /** New value. `None` resets the entry to its default value. */
+  value: string | null;
+}
+
+interface LocalModRequest {
+  path: string;
+  /** Mod id to install the file as. Detected from the plugin metadata if not set. */
+  id: string | null;
+}
+
+interface LinkRequest {
+  path: string;
+  /** Detected from the plugin assemblies in the directory if not set */
+  id: string | null;
+  copy: boolean;
+  scripts: boolean;
+}
+
+/** Error response body */
+interface ApiError {
+  code: ErrorCode;
+  /** Message for the user */
+  error: string;
+}
+
+type ErrorCode =
+  | "not_found"
+  | "method_not_allowed"
+  | "no_game_selected"
+  | "bad_request"
+  | "forbidden"
+  | "conflict"
+  | "game_running"
+  | "cancelled"
+  | "failed";
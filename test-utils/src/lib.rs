@@ -0,0 +1,132 @@
+//! Fixtures shared by the tests of `ytinu-core` and the end-to-end tests of `ytinu`: a local file
+//! server standing in for all downloads, zip archives and mod entries of the metadata.
+//!
+//! This crate doesn't depend on `ytinu-core`, otherwise the tests of `ytinu-core` would see two
+//! different versions of its types.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Cursor, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+};
+
+use serde_json::{json, Value};
+
+/// Serves files by path on a random local port
+pub struct FileServer {
+    port: u16,
+    files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl FileServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = FileServer {
+            port: listener.local_addr().unwrap().port(),
+            files: Arc::default(),
+            requests: Arc::default(),
+        };
+        let files = Arc::clone(&server.files);
+        let requests = Arc::clone(&server.requests);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                serve(stream, &files, &requests);
+            }
+        });
+        server
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    pub fn add(&self, path: &str, data: impl Into<Vec<u8>>) {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), data.into());
+    }
+
+    /// Serves `https://<host>/<path>` at `/<host>/<path>`, where ytinu looks for it when this
+    /// server is set as its mirror
+    pub fn add_url(&self, url: &str, data: impl Into<Vec<u8>>) {
+        let path = url
+            .strip_prefix("https:/")
+            .expect("Only https URLs are mirrored");
+        self.add(path, data);
+    }
+
+    /// Paths of all requests so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn serve(
+    stream: TcpStream,
+    files: &Mutex<HashMap<String, Vec<u8>>>,
+    requests: &Mutex<Vec<String>>,
+) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok() && header.trim() != "" {
+        header.clear();
+    }
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+    requests.lock().unwrap().push(path.clone());
+    let response = match files.lock().unwrap().get(&path) {
+        Some(data) => {
+            let mut response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                data.len()
+            )
+            .into_bytes();
+            response.extend_from_slice(data);
+            response
+        }
+        None => {
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec()
+        }
+    };
+    let _ = (&stream).write_all(&response);
+}
+
+pub fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        writer
+            .start_file(*name, zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// Metadata entry of a mod downloaded from `url`
+pub fn mod_entry(id: &str, version: &str, url: &str) -> Value {
+    json!({
+        "id": id,
+        "name": id,
+        "download": url,
+        "version": version,
+        "files": null,
+        "source": null,
+        "homepage": null,
+        "description": null,
+        "ytinu_version": null,
+    })
+}
@@ -0,0 +1,629 @@
+//! End-to-end tests that start the ytinu binary against a local metadata and mod server and drive
+//! it through its HTTP API like the UI does.
+//!
+//! Each test gets its own directory below the cargo target directory with a copy of the binary,
+//! the data, config and cache directories and a game directory. Nothing outside of it is touched.
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{mpsc, Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+use ytinu_core::{
+    manager::{BEP_IN_EX_DOWNLOAD_URL, GAME_MODS_URL_BASE, METADATA_URL},
+    signature::{sign, signature_url},
+    utils::hash,
+};
+use ytinu_test_utils::{mod_entry, zip, FileServer};
+
+/// Secret seed of the key the fixtures are signed with. Only used for these tests.
+static TEST_SEED: &str = "e2e0000000000000000000000000000000000000000000000000000000000001";
+static TOKEN_HEADER: &str = "X-Ytinu-Token";
+static TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Serves the downloads of ytinu, which uses it as its mirror
+struct MirrorServer {
+    server: FileServer,
+}
+
+impl MirrorServer {
+    fn start() -> Self {
+        MirrorServer {
+            server: FileServer::start(),
+        }
+    }
+
+    fn add(&self, url: &str, data: impl Into<Vec<u8>>) {
+        self.server.add_url(url, data);
+    }
+
+    /// Serves `url` together with its signature
+    fn add_signed(&self, url: &str, data: impl Into<Vec<u8>>) {
+        let data = data.into();
+        let (signature, _) = sign(&data, TEST_SEED).unwrap();
+        self.add(&signature_url(url), signature);
+        self.add(url, data);
+    }
+
+    fn set_metadata(&self, releases: Value) {
+        let meta = json!({
+            "format": 2,
+            "releases": releases,
+            "messages": [],
+            "games": [{
+                "id": "Desperados3",
+                "name": "Desperados III",
+                "appid": null,
+                "recommended_mods": [],
+            }],
+            "mods": [],
+        });
+        self.add_signed(METADATA_URL, meta.to_string());
+    }
+
+    fn set_game_mods(&self, mods: Value) {
+        let url = format!("{}/{}.json", GAME_MODS_URL_BASE, "Desperados3");
+        self.add_signed(&url, json!({ "mods": mods }).to_string());
+    }
+}
+
+/// Directories of one test
+struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    fn new(name: &str) -> Self {
+        let root = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join("e2e")
+            .join(name);
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in &["bin", "data", "config/ytinu", "cache", "game"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::copy(env!("CARGO_BIN_EXE_ytinu"), root.join("bin/ytinu")).unwrap();
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = json!({
+            "port": port,
+            "check_for_updates": false,
+            "open_ui": "none",
+        });
+        std::fs::write(root.join("config/ytinu/config.json"), config.to_string()).unwrap();
+        Sandbox { root }
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    fn game_path(&self) -> String {
+        self.path("game").to_string_lossy().to_string()
+    }
+
+    fn game_file(&self, path: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.path("game").join(path)).ok()
+    }
+
+    /// The stored `data.json` without its format
+    fn state(&self) -> Value {
+        let data = std::fs::read(self.path("data/ytinu/data.json")).unwrap();
+        serde_json::from_slice::<Value>(&data).unwrap()["data"].clone()
+    }
+
+    fn game_state(&self) -> Value {
+        self.state()["games"]["Desperados3"].clone()
+    }
+
+    /// Command running the ytinu binary of this sandbox
+    fn command(&self, mirror: &MirrorServer) -> Command {
+        let (_, public_key) = sign(b"", TEST_SEED).unwrap();
+        let mut command = Command::new(self.path("bin/ytinu"));
+        command
+            .env("HOME", &self.root)
+            .env("XDG_DATA_HOME", self.path("data"))
+            .env("XDG_CONFIG_HOME", self.path("config"))
+            .env("XDG_CACHE_HOME", self.path("cache"))
+            .env("YTINU_MIRROR", mirror.server.url())
+            .env("YTINU_PUBLIC_KEY", public_key)
+            .env("YTINU_NO_DIALOGS", "1")
+            .stdin(Stdio::null());
+        command
+    }
+
+    /// Starts ytinu and waits until its server accepts requests
+    fn start(&self, mirror: &MirrorServer) -> Ytinu {
+        let mut child = self
+            .command(mirror)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let (url_tx, url_rx) = mpsc::channel();
+        let output: Arc<Mutex<Vec<String>>> = Arc::default();
+        // Errors and warnings are logged to stderr
+        let stdout: Box<dyn Read + Send> = Box::new(child.stdout.take().unwrap());
+        let stderr: Box<dyn Read + Send> = Box::new(child.stderr.take().unwrap());
+        for stream in [stdout, stderr] {
+            let lines = Arc::clone(&output);
+            let url_tx = url_tx.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                    println!("{}", line);
+                    if let Some(url) = line.split("Open the UI at ").nth(1) {
+                        let _ = url_tx.send(url.trim().to_string());
+                    }
+                    lines.lock().unwrap().push(line);
+                }
+            });
+        }
+        let url = url_rx
+            .recv_timeout(TIMEOUT)
+            .expect("ytinu didn't start its server");
+        let (base, token) = url.split_once("/?token=").unwrap();
+        Ytinu {
+            child,
+            output,
+            api: format!("{}/api", base),
+            token: token.to_string(),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+/// A running ytinu process
+struct Ytinu {
+    child: Child,
+    /// Lines logged so far
+    output: Arc<Mutex<Vec<String>>>,
+    api: String,
+    token: String,
+    client: reqwest::blocking::Client,
+}
+
+impl Ytinu {
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> reqwest::Result<reqwest::blocking::Response> {
+        let mut request = self
+            .client
+            .request(method, &format!("{}/{}", self.api, path))
+            .header(TOKEN_HEADER, &self.token);
+        if let Some(body) = body {
+            request = request.body(body.to_string());
+        }
+        request.send()
+    }
+
+    /// Sends a request and returns the status code
+    fn call(&self, method: &str, path: &str, body: Option<Value>) -> u16 {
+        let method = reqwest::Method::from_bytes(method.as_bytes()).unwrap();
+        let response = self.request(method, path, body).unwrap();
+        let status = response.status().as_u16();
+        if status >= 400 {
+            println!(
+                "{} {}: {}",
+                status,
+                path,
+                response.text().unwrap_or_default()
+            );
+        }
+        status
+    }
+
+    /// Waits for the process to exit on its own
+    fn wait(mut self) {
+        wait_for_exit(&mut self.child);
+    }
+
+    fn logged(&self, text: &str) -> usize {
+        let output = self.output.lock().unwrap();
+        output.iter().filter(|line| line.contains(text)).count()
+    }
+
+    fn shutdown(self) {
+        assert_eq!(self.call("POST", "shutdown", None), 204);
+        self.wait();
+    }
+}
+
+impl Drop for Ytinu {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn wait_for_exit(child: &mut Child) -> std::process::ExitStatus {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            return status;
+        }
+        assert!(start.elapsed() < TIMEOUT, "ytinu didn't exit");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn mirror_with_mods() -> MirrorServer {
+    let mirror = MirrorServer::start();
+    mirror.set_metadata(json!({}));
+    mirror.set_game_mods(json!([mod_entry(
+        "Foo",
+        "1.0.0",
+        "https://mods.example/Foo-1.zip"
+    )]));
+    mirror.add(
+        BEP_IN_EX_DOWNLOAD_URL,
+        zip(&[
+            ("BepInEx/core/BepInEx.dll", b"bepinex"),
+            ("doorstop_config.ini", b"[UnityDoorstop]"),
+            ("winhttp.dll", b"doorstop"),
+        ]),
+    );
+    mirror.add(
+        "https://mods.example/Foo-1.zip",
+        zip(&[("Foo.dll", b"foo v1"), ("old.txt", b"old")]),
+    );
+    mirror.add(
+        "https://mods.example/Foo-2.zip",
+        zip(&[("Foo.dll", b"foo v2")]),
+    );
+    mirror
+}
+
+#[test]
+fn manage_mod_loader_and_mods() {
+    let mirror = mirror_with_mods();
+    let sandbox = Sandbox::new("manage_mod_loader_and_mods");
+
+    let ytinu = sandbox.start(&mirror);
+    assert_eq!(
+        ytinu.call("POST", "games", Some(json!("/missing/dir"))),
+        400
+    );
+    assert_eq!(
+        ytinu.call("POST", "games", Some(json!(sandbox.game_path()))),
+        204
+    );
+    assert_eq!(sandbox.state()["selected_game"], "Desperados3");
+
+    assert_eq!(ytinu.call("POST", "game/loader", None), 204);
+    assert_eq!(
+        sandbox.game_file("BepInEx/core/BepInEx.dll").unwrap(),
+        b"bepinex"
+    );
+    assert!(sandbox.game_file("winhttp.dll").is_some());
+    assert_eq!(sandbox.game_state()["bep_in_ex"]["enabled"], true);
+
+    assert_eq!(
+        ytinu.call("PUT", "game/loader/enabled", Some(json!(false))),
+        204
+    );
+    assert!(sandbox.game_file("winhttp.dll").is_none());
+    assert!(sandbox.game_file("BepInEx/winhttp.dll").is_some());
+    assert_eq!(sandbox.game_state()["bep_in_ex"]["enabled"], false);
+    assert_eq!(
+        ytinu.call("PUT", "game/loader/enabled", Some(json!(true))),
+        204
+    );
+    assert!(sandbox.game_file("winhttp.dll").is_some());
+    assert_eq!(sandbox.game_state()["bep_in_ex"]["enabled"], true);
+
+    assert_eq!(ytinu.call("PUT", "game/mods/Unknown", None), 404);
+    assert_eq!(ytinu.call("PUT", "game/mods/Foo", None), 204);
+    assert_eq!(ytinu.call("PUT", "game/mods/Foo", None), 409);
+    assert_eq!(
+        sandbox.game_file("BepInEx/plugins/Foo/Foo.dll").unwrap(),
+        b"foo v1"
+    );
+    assert_eq!(sandbox.game_state()["mods"]["Foo"]["version"], "1.0.0");
+    ytinu.shutdown();
+
+    // The config written by the test is upgraded to the current format
+    let config = std::fs::read(sandbox.path("config/ytinu/config.json")).unwrap();
+    let config: Value = serde_json::from_slice(&config).unwrap();
+    assert_eq!(config["format"], 1);
+    assert_eq!(config["data"]["open_ui"], "none");
+    assert!(sandbox.path("config/ytinu/config.json.format0").exists());
+
+    // A new version is picked up on the next start
+    mirror.set_game_mods(json!([mod_entry(
+        "Foo",
+        "2.0.0",
+        "https://mods.example/Foo-2.zip"
+    )]));
+    let ytinu = sandbox.start(&mirror);
+    assert_eq!(ytinu.call("POST", "game/mods/Foo/update", None), 204);
+    assert_eq!(
+        sandbox.game_file("BepInEx/plugins/Foo/Foo.dll").unwrap(),
+        b"foo v2"
+    );
+    assert!(sandbox.game_file("BepInEx/plugins/Foo/old.txt").is_none());
+    assert_eq!(sandbox.game_state()["mods"]["Foo"]["version"], "2.0.0");
+
+    assert_eq!(ytinu.call("DELETE", "game/mods/Foo", None), 204);
+    assert!(!sandbox.path("game/BepInEx/plugins/Foo").exists());
+    assert!(sandbox.game_state()["mods"].as_object().unwrap().is_empty());
+    assert!(sandbox.game_file("BepInEx/core/BepInEx.dll").is_some());
+    ytinu.shutdown();
+}
+
+#[test]
+fn second_launch_hands_off_to_running_instance() {
+    let mirror = mirror_with_mods();
+    let sandbox = Sandbox::new("second_launch_hands_off_to_running_instance");
+    let ytinu = sandbox.start(&mirror);
+    assert!(sandbox.path("data/ytinu/instance.json").exists());
+
+    let mut second = sandbox
+        .command(&mirror)
+        .args(["--ui", "none"])
+        .spawn()
+        .unwrap();
+    assert!(wait_for_exit(&mut second).success());
+    // The running instance shows its UI again
+    assert_eq!(ytinu.logged("Request: POST /api/activate"), 1);
+    assert_eq!(ytinu.logged("Open the UI at"), 2);
+    assert_eq!(ytinu.call("GET", "state", None), 200);
+
+    ytinu.shutdown();
+    assert!(!sandbox.path("data/ytinu/instance.json").exists());
+}
+
+#[test]
+fn install_link_is_passed_to_running_instance() {
+    let mirror = mirror_with_mods();
+    let sandbox = Sandbox::new("install_link_is_passed_to_running_instance");
+    let ytinu = sandbox.start(&mirror);
+    // Only release builds register the handler
+    assert!(!sandbox.path("data/applications/ytinu.desktop").exists());
+
+    let mut second = sandbox
+        .command(&mirror)
+        .arg("ytinu://install/Desperados3/Foo@1.0.0")
+        .spawn()
+        .unwrap();
+    assert!(wait_for_exit(&mut second).success());
+    assert_eq!(
+        ytinu.logged("Received install link: ytinu://install/Desperados3/Foo@1.0.0"),
+        1
+    );
+    // No game is set up, so the link is rejected before asking the user
+    let start = Instant::now();
+    while ytinu.logged("Can't install 'Foo'") == 0 {
+        assert!(start.elapsed() < TIMEOUT, "link wasn't handled");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let request = json!({ "ui": "none", "install": "ytinu://remove/Desperados3/Foo" });
+    assert_eq!(ytinu.call("POST", "activate", Some(request)), 400);
+
+    ytinu.shutdown();
+}
+
+#[test]
+fn unauthorized_requests_are_rejected() {
+    let mirror = mirror_with_mods();
+    let sandbox = Sandbox::new("unauthorized_requests_are_rejected");
+    let ytinu = sandbox.start(&mirror);
+    let url = format!("{}/games", ytinu.api);
+    let port = reqwest::Url::parse(&url).unwrap().port().unwrap();
+    let body = json!(sandbox.game_path()).to_string();
+    let post = || ytinu.client.post(&url).body(body.clone());
+
+    let requests = [
+        ("missing token", post()),
+        ("wrong token", post().header(TOKEN_HEADER, "0".repeat(64))),
+        (
+            "foreign host",
+            post()
+                .header(TOKEN_HEADER, &ytinu.token)
+                .header("Host", format!("ytinu.example:{}", port)),
+        ),
+        (
+            "foreign origin",
+            post()
+                .header(TOKEN_HEADER, &ytinu.token)
+                .header("Origin", "http://ytinu.example"),
+        ),
+    ];
+    for (case, request) in requests {
+        assert_eq!(request.send().unwrap().status().as_u16(), 403, "{}", case);
+    }
+    assert_eq!(ytinu.logged("Rejected request"), 4);
+    assert!(sandbox.state()["selected_game"].is_null());
+
+    let allowed = post()
+        .header(TOKEN_HEADER, &ytinu.token)
+        .header("Origin", format!("http://localhost:{}", port));
+    assert_eq!(allowed.send().unwrap().status().as_u16(), 204);
+    assert_eq!(sandbox.state()["selected_game"], "Desperados3");
+    ytinu.shutdown();
+}
+
+#[test]
+fn unusable_state_is_not_overwritten() {
+    let mirror = mirror_with_mods();
+    let sandbox = Sandbox::new("unusable_state_is_not_overwritten");
+    let data = json!({ "format": 999, "data": {} }).to_string();
+    std::fs::create_dir_all(sandbox.path("data/ytinu")).unwrap();
+    std::fs::write(sandbox.path("data/ytinu/data.json"), &data).unwrap();
+
+    let output = sandbox.command(&mirror).output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("data.json can't be used"), "{}", stderr);
+    assert_eq!(
+        std::fs::read_to_string(sandbox.path("data/ytinu/data.json")).unwrap(),
+        data
+    );
+}
+
+/// Serves an update to 99.0.0 whose executable is `new_version`
+fn mirror_with_update(new_version: &[u8]) -> MirrorServer {
+    let url = "https://github.com/ytinu-mods/ytinu/releases/download/v99.0.0/ytinu";
+    let mirror = mirror_with_mods();
+    mirror.set_metadata(json!({
+        "stable": {
+            "version": "99.0.0",
+            "downloads": { "linux": url, "macos": url },
+            "hashes": { "linux": hash(new_version), "macos": hash(new_version) },
+        }
+    }));
+    mirror.add_signed(url, new_version.to_vec());
+    mirror
+}
+
+#[test]
+fn metadata_follows_update_channel_and_skipped_version() {
+    let mirror = mirror_with_mods();
+    mirror.set_metadata(json!({
+        "stable": { "version": "99.0.0", "downloads": {} },
+        "beta": { "version": "100.0.0-beta.1", "downloads": {} },
+    }));
+    let sandbox = Sandbox::new("metadata_follows_update_channel_and_skipped_version");
+    let ytinu = sandbox.start(&mirror);
+    let get = |path: &str| -> Value {
+        let response = ytinu.request(reqwest::Method::GET, path, None).unwrap();
+        response.json().unwrap()
+    };
+
+    let metadata = get("metadata");
+    assert_eq!(metadata["version"], "99.0.0");
+    assert_eq!(metadata["update"], true);
+
+    assert_eq!(
+        ytinu.call("PUT", "update/skipped", Some(json!("99.0.0"))),
+        204
+    );
+    assert_eq!(get("metadata")["update"], false);
+    assert_eq!(get("update")["skipped"], true);
+
+    let mut config = get("config");
+    config["update_channel"] = json!("beta");
+    assert_eq!(ytinu.call("PUT", "config", Some(config)), 204);
+    let metadata = get("metadata");
+    assert_eq!(metadata["version"], "100.0.0-beta.1");
+    assert_eq!(metadata["update"], true);
+    assert_eq!(get("update")["release"]["version"], "100.0.0-beta.1");
+
+    ytinu.shutdown();
+}
+
+/// Installs the available update and waits until the previous process exited
+fn install_update(sandbox: &Sandbox, mirror: &MirrorServer) {
+    let ytinu = sandbox.start(mirror);
+    let info: Value = ytinu
+        .request(reqwest::Method::GET, "update", None)
+        .unwrap()
+        .json()
+        .unwrap();
+    assert_eq!(info["release"]["version"], "99.0.0");
+
+    // ytinu restarts before answering
+    let _ = ytinu.request(reqwest::Method::POST, "update", None);
+    ytinu.wait();
+}
+
+#[test]
+#[cfg(unix)]
+fn self_update() {
+    // The new version only confirms its start like ytinu does once its server is running
+    let new_version = b"#!/bin/sh\nrm \"$(dirname \"$0\")/ytinu_update.json\"\n".to_vec();
+    let mirror = mirror_with_update(&new_version);
+    let sandbox = Sandbox::new("self_update");
+    let old_version = std::fs::read(sandbox.path("bin/ytinu")).unwrap();
+
+    install_update(&sandbox, &mirror);
+    assert_eq!(
+        std::fs::read(sandbox.path("bin/ytinu")).unwrap(),
+        new_version
+    );
+    assert!(std::fs::read(sandbox.path("bin/ytinu_old")).unwrap() == old_version);
+    assert!(!sandbox.path("bin/ytinu_update.json").exists());
+}
+
+#[test]
+fn update_without_checksum_is_refused() {
+    let url = "https://github.com/ytinu-mods/ytinu/releases/download/v99.0.0/ytinu";
+    let mirror = mirror_with_mods();
+    mirror.set_metadata(json!({
+        "stable": {
+            "version": "99.0.0",
+            "downloads": { "linux": url, "macos": url, "windows": url },
+        }
+    }));
+    mirror.add_signed(url, b"unverified".to_vec());
+    let sandbox = Sandbox::new("update_without_checksum_is_refused");
+    let old_version = std::fs::read(sandbox.path("bin/ytinu")).unwrap();
+
+    let ytinu = sandbox.start(&mirror);
+    assert_eq!(ytinu.call("POST", "update", None), 500);
+    assert_eq!(ytinu.logged("No update checksum for OS"), 1);
+    ytinu.shutdown();
+    assert!(std::fs::read(sandbox.path("bin/ytinu")).unwrap() == old_version);
+    assert!(!sandbox.path("bin/ytinu_old").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn failed_self_update_is_restored() {
+    // The new version exits without confirming its start
+    let new_version = b"#!/bin/sh\nexit 1\n".to_vec();
+    let mirror = mirror_with_update(&new_version);
+    let sandbox = Sandbox::new("failed_self_update");
+    let old_version = std::fs::read(sandbox.path("bin/ytinu")).unwrap();
+
+    install_update(&sandbox, &mirror);
+    assert!(std::fs::read(sandbox.path("bin/ytinu")).unwrap() == old_version);
+    assert!(!sandbox.path("bin/ytinu_old").exists());
+    assert!(!sandbox.path("bin/ytinu_update.json").exists());
+
+    // The restored version was started again
+    let instance_path = sandbox.path("data/ytinu/instance.json");
+    let start = Instant::now();
+    let instance: Value = loop {
+        if let Some(instance) = std::fs::read(&instance_path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+        {
+            break instance;
+        }
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "The previous version wasn't started"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    let status = reqwest::blocking::Client::new()
+        .post(&format!(
+            "http://127.0.0.1:{}/api/shutdown",
+            instance["port"]
+        ))
+        .header(TOKEN_HEADER, instance["token"].as_str().unwrap())
+        .send()
+        .unwrap()
+        .status();
+    assert_eq!(status, 204);
+    while instance_path.exists() {
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "The previous version didn't exit"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}